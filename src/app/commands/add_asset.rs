@@ -3,8 +3,12 @@ use crate::app::{
     util::fetch_image_from_attachment, AppContext, AppError,
 };
 use gartic_on_demand::services::{
-    database::assets::{Asset, AssetKind, ImageRepository},
-    image_processing::{normalize_image, RgbaConvert},
+    database::{
+        assets::{Asset, AssetKind, ImageRepository},
+        theme::ThemeRepository,
+    },
+    gamemodes::Mode,
+    image_processing::{fit_image, RgbaConvert},
     provider::Provider,
 };
 use poise::serenity_prelude::{Attachment, AttachmentType, ChannelId, ReactionType, UserId};
@@ -24,10 +28,12 @@ pub async fn add_asset(
     #[description = "Asset"] attachment: Attachment,
     #[description = "Asset kind"] kind: AssetKindArg,
     #[description = "Asset author"] author: UserId,
+    #[description = "Theme the asset belongs to"] theme: Option<String>,
+    #[description = "Mildest rating to use the asset in"] rating: Option<String>,
 ) -> Result<(), AppError> {
     let mut rsx = ResponseContext::new(ctx);
     rsx.init().await?;
-    if let Err(e) = process(&mut rsx, ctx, attachment, kind, author).await {
+    if let Err(e) = process(&mut rsx, ctx, attachment, kind, author, theme, rating).await {
         error!(error = ?e);
         rsx.respond(|b| b.content(e.for_user())).await?
     }
@@ -41,14 +47,27 @@ async fn process(
     attachment: Attachment,
     kind: AssetKindArg,
     author: UserId,
+    theme: Option<String>,
+    rating: Option<String>,
 ) -> Result<(), AppError> {
     let user = ctx.author();
     has_admin(&ctx, user).await?;
 
+    let rating = match rating {
+        Some(name) => CONFIG.ratings.level(&name).map_user("Unknown rating")?,
+        None => 0,
+    };
+
+    if let Some(theme) = &theme {
+        let tr: ThemeRepository = ctx.data().get();
+        tr.get(theme).await.map_user("Unknown theme")?;
+    }
+
     let image = fetch_image_from_attachment(&attachment)
         .await
         .map_user("Attachment is not an image")?;
-    let image = normalize_image(&image, CONFIG.image.width, CONFIG.image.height);
+    let canvas = Mode::max_canvas();
+    let image = fit_image(&image, canvas.width, canvas.height);
     let image = AttachmentType::Bytes {
         data: Cow::Owned(image.to_png().to_vec()),
         filename: ctx.id().to_string() + ".png",
@@ -59,17 +78,25 @@ async fn process(
         .send_message(ctx, |m| {
             m.add_file(image)
                 .content(format!(
-                    "Created by <@{}>, added by <@{}>",
-                    author.0, user.id.0
+                    "Created by <@{}>, added by <@{}>{}",
+                    author.0,
+                    user.id.0,
+                    theme
+                        .as_ref()
+                        .map(|theme| format!(", theme {}", theme))
+                        .unwrap_or_default()
                 ))
                 .reactions([ReactionType::Unicode(CONFIG.reactions.delete.clone())])
         })
         .await?;
 
     let ir: ImageRepository = ctx.data().get();
-    ir.create(message.id.0, Asset::new(kind, user.id.0, author.0))
-        .await
-        .map_internal("Failed to add image to database")?;
+    ir.create(
+        message.id.0,
+        Asset::new(kind, user.id.0, author.0, theme, rating),
+    )
+    .await
+    .map_internal("Failed to add image to database")?;
 
     rcx.respond(|f| f.content("Added")).await?;
 
@@ -0,0 +1,471 @@
+use crate::app::{
+    config::CONFIG,
+    error::ConvertError,
+    permission::has_admin,
+    rendering::{ModeRenderer, RoundRenderer},
+    response::ResponseContext,
+    util::{dm_prompt, pick_session, session_destination, spoiler},
+    AppContext, AppError,
+};
+use gartic_on_demand::services::{
+    database::{
+        attempt::{Active, AttemptRepository},
+        audit::{AuditAction, AuditEntry, AuditRepository},
+        round::{RoundRepository, RoundWithAttempts},
+        stats::StatsRepository,
+        theme::ThemeRepository,
+        user::{User, UserRepository},
+        Record, ThingToU64,
+    },
+    gamemodes::GameLogic,
+    provider::Provider,
+    status_update::StatusUpdateWaker,
+};
+use poise::serenity_prelude::{
+    self as serenity, Attachment, AttachmentType, MessageId, ReactionType,
+};
+use std::borrow::Cow;
+use tracing::error;
+
+/// Repair broken games
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "replace_image",
+        "expire",
+        "cancel",
+        "reassign",
+        "delete_chain",
+        "audit",
+        "export"
+    )
+)]
+pub async fn admin(_ctx: AppContext<'_>) -> Result<(), AppError> {
+    Ok(())
+}
+
+/// Replace the image of an approved submission
+#[poise::command(slash_command, guild_only)]
+async fn replace_image(
+    ctx: AppContext<'_>,
+    #[description = "Link or id of the published image"] image: String,
+    #[description = "Replacement image"] attachment: Attachment,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_replace_image(&mut rsx, ctx, image, attachment).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Expire the active game of a player
+#[poise::command(slash_command, guild_only)]
+async fn expire(
+    ctx: AppContext<'_>,
+    #[description = "Player whose game expires"] player: serenity::User,
+    #[description = "Game session, needed when the player has several"] session: Option<String>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_end(&mut rsx, ctx, player, session, true).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Cancel the active game of a player
+#[poise::command(slash_command, guild_only)]
+async fn cancel(
+    ctx: AppContext<'_>,
+    #[description = "Player whose game is cancelled"] player: serenity::User,
+    #[description = "Game session, needed when the player has several"] session: Option<String>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_end(&mut rsx, ctx, player, session, false).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Hand the active game of a player over to another player
+#[poise::command(slash_command, guild_only)]
+async fn reassign(
+    ctx: AppContext<'_>,
+    #[description = "Current player"] from: serenity::User,
+    #[description = "New player"] to: serenity::User,
+    #[description = "Game session, needed when they have several"] session: Option<String>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_reassign(&mut rsx, ctx, from, to, session).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Delete the whole game containing an approved submission
+#[poise::command(slash_command, guild_only)]
+async fn delete_chain(
+    ctx: AppContext<'_>,
+    #[description = "Link or id of any published image of the game"] image: String,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_delete_chain(&mut rsx, ctx, image).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Show the most recent admin actions
+#[poise::command(slash_command, guild_only)]
+async fn audit(ctx: AppContext<'_>) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_audit(&mut rsx, ctx).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Export completed games as CSV
+#[poise::command(slash_command, guild_only)]
+async fn export(
+    ctx: AppContext<'_>,
+    #[description = "Only games of this theme"] theme: Option<String>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_export(&mut rsx, ctx, theme).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn admin_user(ctx: AppContext<'_>) -> Result<Record<User>, AppError> {
+    let discord_user = ctx.author();
+    has_admin(&ctx, discord_user).await?;
+    let ur: UserRepository = ctx.data().get();
+    ur.create_or_update_user(discord_user.id.0, &discord_user.name)
+        .await
+        .map_internal("Failed to update user")
+}
+
+async fn player(ctx: AppContext<'_>, user: &serenity::User) -> Result<Record<User>, AppError> {
+    let ur: UserRepository = ctx.data().get();
+    ur.create_or_update_user(user.id.0, &user.name)
+        .await
+        .map_internal("Failed to update user")
+}
+
+/// Active session of a player, lists their sessions instead when several could be meant.
+async fn player_session(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    user: &Record<User>,
+    session: Option<&str>,
+) -> Result<Option<RoundWithAttempts<Active>>, AppError> {
+    let rr: RoundRepository = ctx.data().get();
+    let rounds = rr
+        .get_active_rounds(user)
+        .await
+        .map_internal("Failed to find existing sessions")?;
+    if session.is_none() && rounds.len() > 1 {
+        let sessions = rounds
+            .iter()
+            .map(|round| {
+                format!(
+                    "`{}` {:?} mode round {}",
+                    round.attempt.id.id.to_raw(),
+                    round.round.mode,
+                    round.round.round_no + 1
+                )
+            })
+            .collect::<Vec<_>>();
+        rsx.respond(|b| {
+            b.content(format!(
+                "<@{}> has several game sessions, pick one with the `session` option:\n{}",
+                user.id(),
+                sessions.join("\n")
+            ))
+        })
+        .await?;
+        return Ok(None);
+    }
+    pick_session(rounds, session).map(Some)
+}
+
+/// Accepts both message links and bare message ids.
+fn parse_image(image: &str) -> Result<u64, AppError> {
+    image
+        .rsplit('/')
+        .next()
+        .and_then(|id| id.trim().parse().ok())
+        .map_user("Expected a message link or id")
+}
+
+async fn process_replace_image(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    image: String,
+    attachment: Attachment,
+) -> Result<(), AppError> {
+    let admin = admin_user(ctx).await?;
+    let ar: AttemptRepository = ctx.data().get();
+    let old_image = parse_image(&image)?;
+    let round = ar
+        .get_approved_attempt(old_image)
+        .await
+        .map_user("No approved submission with this image")?;
+
+    let mode = round.round.mode;
+    let (attachment, content) = if round.round.round_no == mode.last_round() {
+        let attachment = mode
+            .render_complete_image(&ctx, &round, &ctx.data().get(), &attachment)
+            .await?;
+        let attachment = spoiler(attachment, CONFIG.ratings.get(round.round.rating).spoiler);
+        (attachment, round.render_complete_text())
+    } else {
+        let attachment = mode
+            .render_partial_image(round.round.round_no, &attachment)
+            .await?;
+        let attachment = spoiler(attachment, CONFIG.spoilers.partial);
+        (attachment, round.render_public_partial_text())
+    };
+    let channel = session_destination(&round);
+    let message = channel
+        .send_message(ctx, |m| {
+            m.add_file(attachment)
+                .content(content)
+                .reactions([ReactionType::Unicode(CONFIG.reactions.report.clone())])
+        })
+        .await?;
+    ar.replace_approved_image(&admin, old_image, message.id.0)
+        .await
+        .map_internal("Failed to replace image")?;
+    channel.delete_message(ctx, MessageId(old_image)).await?;
+
+    rsx.respond(|b| b.content(format!("Replaced with {}", message.link())))
+        .await?;
+    Ok(())
+}
+
+async fn process_end(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    discord_user: serenity::User,
+    session: Option<String>,
+    expire: bool,
+) -> Result<(), AppError> {
+    let admin = admin_user(ctx).await?;
+    let ar: AttemptRepository = ctx.data().get();
+    let user = player(ctx, &discord_user).await?;
+    let Some(round) = player_session(rsx, ctx, &user, session.as_deref()).await? else {
+        return Ok(());
+    };
+    let attempt = &round.attempt.id;
+    let verb = match expire {
+        true => {
+            ar.force_expire_active_attempt(&admin, &user, attempt)
+                .await
+                .map_user("Player has no active game")?;
+            "Expired"
+        }
+        false => {
+            ar.force_cancel_active_attempt(&admin, &user, attempt)
+                .await
+                .map_user("Player has no active game")?;
+            "Cancelled"
+        }
+    };
+
+    rsx.respond(|b| {
+        b.content(format!(
+            "{} the {:?} mode game of <@{}>",
+            verb,
+            round.round.mode,
+            user.id()
+        ))
+    })
+    .await?;
+    let waker: StatusUpdateWaker = ctx.data().get();
+    waker.wake();
+    Ok(())
+}
+
+async fn process_reassign(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    from: serenity::User,
+    to: serenity::User,
+    session: Option<String>,
+) -> Result<(), AppError> {
+    let admin = admin_user(ctx).await?;
+    let ar: AttemptRepository = ctx.data().get();
+    let from = player(ctx, &from).await?;
+    let to = player(ctx, &to).await?;
+    let Some(active) = player_session(rsx, ctx, &from, session.as_deref()).await? else {
+        return Ok(());
+    };
+    let round = ar
+        .reassign_active_attempt(
+            &admin,
+            &from,
+            &active.attempt.id,
+            &to,
+            CONFIG.sessions.max_active,
+            &CONFIG.modes.get(active.round.mode).fairness,
+        )
+        .await
+        .map_user("Only an active game can be handed to a player free to join its round, with a free session and no game of its mode")?;
+
+    if let Err(e) = dm_prompt(ctx.serenity_context(), &ctx.data().get(), &round).await {
+        error!(error = %e, "Failed to send reassigned prompt");
+    }
+    rsx.respond(|b| {
+        b.content(format!(
+            "Handed the {:?} mode game of <@{}> to <@{}>",
+            round.round.mode,
+            from.id(),
+            to.id()
+        ))
+    })
+    .await?;
+    let waker: StatusUpdateWaker = ctx.data().get();
+    waker.wake();
+    Ok(())
+}
+
+async fn process_delete_chain(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    image: String,
+) -> Result<(), AppError> {
+    let admin = admin_user(ctx).await?;
+    let ar: AttemptRepository = ctx.data().get();
+    let rr: RoundRepository = ctx.data().get();
+    let round = ar
+        .get_approved_attempt(parse_image(&image)?)
+        .await
+        .map_user("No approved submission with this image")?;
+    let deleted = rr
+        .delete_chain(&admin, &round.round.id)
+        .await
+        .map_internal("Failed to delete game")?;
+
+    rsx.respond(|b| b.content(format!("Deleted {} rounds", deleted.len())))
+        .await?;
+    let waker: StatusUpdateWaker = ctx.data().get();
+    waker.wake();
+    Ok(())
+}
+
+async fn process_audit(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(), AppError> {
+    admin_user(ctx).await?;
+    let ar: AuditRepository = ctx.data().get();
+    let entries = ar
+        .recent(10)
+        .await
+        .map_internal("Failed to fetch audit log")?;
+    let mut content = entries
+        .iter()
+        .map(|e| audit_entry_to_string(e))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if content.is_empty() {
+        content.push_str("None");
+    }
+    rsx.respond(|b| b.content(content)).await?;
+    Ok(())
+}
+
+async fn process_export(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    theme: Option<String>,
+) -> Result<(), AppError> {
+    admin_user(ctx).await?;
+    if let Some(theme) = &theme {
+        let tr: ThemeRepository = ctx.data().get();
+        tr.get(theme).await.map_user("Unknown theme")?;
+    }
+    let sr: StatsRepository = ctx.data().get();
+    let chains = sr
+        .get_complete_chains(theme.as_deref())
+        .await
+        .map_internal("Failed to export games")?;
+    let mut csv = "game,mode,rating,theme,round,author,image\n".to_owned();
+    for chain in &chains {
+        let round = &chain.round;
+        let attempts = chain.previous.iter().chain([&chain.attempt]);
+        for (round_no, attempt) in attempts.enumerate() {
+            csv.push_str(&format!(
+                "{},{:?},{},{},{},{},{}\n",
+                round.id.id,
+                round.mode,
+                csv_field(&CONFIG.ratings.get(round.rating).name),
+                csv_field(round.theme.as_deref().unwrap_or_default()),
+                round_no + 1,
+                attempt.who.to_u64(),
+                attempt.state.what
+            ));
+        }
+    }
+    let attachment = AttachmentType::Bytes {
+        data: Cow::Owned(csv.into_bytes()),
+        filename: "games.csv".to_owned(),
+    };
+    rsx.respond(|b| {
+        b.attachment(attachment)
+            .content(format!("Exported {} games", chains.len()))
+    })
+    .await?;
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+fn audit_entry_to_string(entry: &AuditEntry) -> String {
+    let action = match &entry.action {
+        AuditAction::ReplaceImage { old, new } => {
+            format!("replaced image {} with {}", old, new)
+        }
+        AuditAction::ExpireAttempt { user } => {
+            format!("expired the game of <@{}>", user.to_u64())
+        }
+        AuditAction::CancelAttempt { user } => {
+            format!("cancelled the game of <@{}>", user.to_u64())
+        }
+        AuditAction::ReassignAttempt { from, to } => format!(
+            "handed the game of <@{}> to <@{}>",
+            from.to_u64(),
+            to.to_u64()
+        ),
+        AuditAction::DeleteChain { round } => format!("deleted the game of {}", round),
+    };
+    format!(
+        "- <t:{}:f> <@{}> {}",
+        entry.created_at.timestamp(),
+        entry.actor.to_u64(),
+        action
+    )
+}
@@ -0,0 +1,107 @@
+use crate::app::{
+    config::CONFIG,
+    error::ConvertError,
+    rendering::RoundRenderer,
+    response::ResponseContext,
+    util::{fetch_raw_image_from_attachment, raw_image_to_attachment},
+    AppContext, AppError,
+};
+use chrono::Utc;
+use gartic_on_demand::services::{
+    database::{
+        appeal::AppealRepository, attempt::AttemptRepository, user::UserRepository, ThingToU64,
+    },
+    provider::Provider,
+};
+use poise::serenity_prelude::{Mentionable, MessageId, ReactionType, UserId};
+use tracing::error;
+
+/// Appeal your most recently rejected submission
+#[poise::command(slash_command, guild_only)]
+pub async fn appeal(
+    ctx: AppContext<'_>,
+    #[description = "Anything the moderators should know"] message: Option<String>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process(&mut rsx, ctx, message).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    message: Option<String>,
+) -> Result<(), AppError> {
+    let ar: AttemptRepository = ctx.data().get();
+    let ur: UserRepository = ctx.data().get();
+    let ap: AppealRepository = ctx.data().get();
+    let discord_user = ctx.author();
+    let user = ur
+        .create_or_update_user(discord_user.id.0, &discord_user.name)
+        .await
+        .map_internal("Failed to update user")?;
+
+    let round = ar
+        .get_rejected_attempt_since(&user, Utc::now() - CONFIG.appeals.window)
+        .await
+        .map_user("No recently rejected submission")?;
+    let appealed = ap
+        .is_appealed(&round.attempt.id)
+        .await
+        .map_internal("Failed to check appeals")?;
+    if appealed {
+        rsx.respond(|f| f.content("This submission was already appealed"))
+            .await?;
+        return Ok(());
+    }
+
+    let rejected = CONFIG
+        .channels
+        .rejects
+        .message(ctx, MessageId(round.attempt.state.what))
+        .await?;
+    let raw_image = fetch_raw_image_from_attachment(&rejected.attachments[0])
+        .await
+        .map_internal("Failed to fetch image")?;
+    let mut content = format!(
+        "Appeal by {} of {}, rejected by {}",
+        discord_user.mention(),
+        round.render_partial_text(),
+        UserId(round.attempt.state.who.to_u64()).mention()
+    );
+    if let Some(message) = &message {
+        content.push_str(&format!("\n> {message}"));
+    }
+    let queued = CONFIG
+        .channels
+        .appeals
+        .send_message(ctx, |m| {
+            m.add_file(raw_image_to_attachment(raw_image.into()))
+                .content(content)
+                .reactions([
+                    ReactionType::Unicode(CONFIG.reactions.accept.clone()),
+                    ReactionType::Unicode(CONFIG.reactions.reject.clone()),
+                ])
+        })
+        .await?;
+    ap.create(
+        queued.id.0,
+        &user,
+        &round.attempt.id,
+        &round.attempt.state.who,
+        message,
+    )
+    .await
+    .map_internal("Failed to create appeal")?;
+
+    rsx.respond(|f| {
+        f.content("Appeal submitted, a different moderator will review your submission")
+    })
+    .await?;
+    Ok(())
+}
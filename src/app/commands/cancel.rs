@@ -1,6 +1,11 @@
-use crate::app::{error::ConvertError, response::ResponseContext, AppContext, AppError};
+use crate::app::{
+    error::ConvertError,
+    response::ResponseContext,
+    util::{autocomplete_session, pick_session},
+    AppContext, AppError,
+};
 use gartic_on_demand::services::{
-    database::{attempt::AttemptRepository, user::UserRepository},
+    database::{attempt::AttemptRepository, round::RoundRepository, user::UserRepository},
     provider::Provider,
     status_update::StatusUpdateWaker,
 };
@@ -8,10 +13,15 @@ use tracing::error;
 
 /// Cancel the current game session
 #[poise::command(slash_command, guild_only)]
-pub async fn cancel(ctx: AppContext<'_>) -> Result<(), AppError> {
+pub async fn cancel(
+    ctx: AppContext<'_>,
+    #[description = "Game session to cancel"]
+    #[autocomplete = "autocomplete_session"]
+    session: Option<String>,
+) -> Result<(), AppError> {
     let mut rsx = ResponseContext::new(ctx);
     rsx.init().await?;
-    if let Err(e) = process(&mut rsx, ctx).await {
+    if let Err(e) = process(&mut rsx, ctx, session).await {
         error!(error = ?e);
         rsx.respond(|b| b.content(e.for_user())).await?
     }
@@ -19,15 +29,25 @@ pub async fn cancel(ctx: AppContext<'_>) -> Result<(), AppError> {
     Ok(())
 }
 
-async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(), AppError> {
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    session: Option<String>,
+) -> Result<(), AppError> {
     let ar: AttemptRepository = ctx.data().get();
     let ur: UserRepository = ctx.data().get();
+    let rr: RoundRepository = ctx.data().get();
     let user = ctx.author();
     let user = ur
         .create_or_update_user(user.id.0, &user.name)
         .await
         .map_internal("Failed to update user")?;
-    ar.cancel_active_attempt(&user)
+    let rounds = rr
+        .get_active_rounds(&user)
+        .await
+        .map_internal("Failed to find existing sessions")?;
+    let round = pick_session(rounds, session.as_deref())?;
+    ar.cancel_active_attempt(&user, &round.attempt.id)
         .await
         .map_user("No previous session")?;
     rsx.respond(|f| f.content("Cancelled previous session"))
@@ -1,5 +1,8 @@
 use crate::app::{
-    error::ConvertError, response::ResponseContext, util::respond_with_prompt, AppContext, AppError,
+    error::ConvertError,
+    response::ResponseContext,
+    util::{autocomplete_session, pick_session, respond_with_prompt},
+    AppContext, AppError,
 };
 use gartic_on_demand::services::{
     database::{attempt::AttemptRepository, round::RoundRepository, user::UserRepository},
@@ -10,10 +13,15 @@ use tracing::error;
 
 /// Get current game session
 #[poise::command(slash_command, guild_only)]
-pub async fn current(ctx: AppContext<'_>) -> Result<(), AppError> {
+pub async fn current(
+    ctx: AppContext<'_>,
+    #[description = "Game session to show, every session by default"]
+    #[autocomplete = "autocomplete_session"]
+    session: Option<String>,
+) -> Result<(), AppError> {
     let mut rsx = ResponseContext::new(ctx);
     rsx.init().await?;
-    if let Err(e) = process(&mut rsx, ctx).await {
+    if let Err(e) = process(&mut rsx, ctx, session).await {
         error!(error = ?e);
         rsx.respond(|b| b.content(e.for_user())).await?
     }
@@ -21,7 +29,11 @@ pub async fn current(ctx: AppContext<'_>) -> Result<(), AppError> {
     Ok(())
 }
 
-async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(), AppError> {
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    session: Option<String>,
+) -> Result<(), AppError> {
     let ar: AttemptRepository = ctx.data().get();
     let ur: UserRepository = ctx.data().get();
     let rr: RoundRepository = ctx.data().get();
@@ -35,10 +47,18 @@ async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(
         .map_internal("Failed to unlock expired sessions")?;
     let waker: StatusUpdateWaker = ctx.data().get();
     waker.wake();
-    let lobby = rr
-        .get_active_round(&user)
+    let rounds = rr
+        .get_active_rounds(&user)
         .await
-        .map_user("No current game")?;
-    respond_with_prompt(rsx, &ctx, &lobby, false).await?;
+        .map_internal("Failed to find existing sessions")?;
+    let rounds = match session {
+        Some(session) => vec![pick_session(rounds, Some(&session))?],
+        None if rounds.is_empty() => None.map_user("No current game")?,
+        None => rounds,
+    };
+    for round in &rounds {
+        respond_with_prompt(rsx, &ctx, round, false).await?;
+        rsx.reset();
+    }
     Ok(())
 }
@@ -1,19 +1,30 @@
 use crate::app::{
-    error::ConvertError, response::ResponseContext, util::respond_with_prompt, AppContext, AppError,
+    config::CONFIG,
+    error::ConvertError,
+    permission::is_admin,
+    reliability::time_limit_for,
+    response::ResponseContext,
+    util::{autocomplete_session, pick_session, respond_with_prompt},
+    AppContext, AppError,
 };
+use chrono::Utc;
 use gartic_on_demand::services::{
     database::{attempt::AttemptRepository, round::RoundRepository, user::UserRepository},
-    gamemodes::GameLogic,
     provider::Provider,
 };
 use tracing::error;
 
 /// Reset the expiry timer on current game session
 #[poise::command(slash_command, guild_only)]
-pub async fn extend(ctx: AppContext<'_>) -> Result<(), AppError> {
+pub async fn extend(
+    ctx: AppContext<'_>,
+    #[description = "Game session to extend"]
+    #[autocomplete = "autocomplete_session"]
+    session: Option<String>,
+) -> Result<(), AppError> {
     let mut rsx = ResponseContext::new(ctx);
     rsx.init().await?;
-    if let Err(e) = process(&mut rsx, ctx).await {
+    if let Err(e) = process(&mut rsx, ctx, session).await {
         error!(error = ?e);
         rsx.respond(|b| b.content(e.for_user())).await?
     }
@@ -21,7 +32,11 @@ pub async fn extend(ctx: AppContext<'_>) -> Result<(), AppError> {
     Ok(())
 }
 
-async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(), AppError> {
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    session: Option<String>,
+) -> Result<(), AppError> {
     let ar: AttemptRepository = ctx.data().get();
     let ur: UserRepository = ctx.data().get();
     let rr: RoundRepository = ctx.data().get();
@@ -31,13 +46,38 @@ async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(
         .await
         .map_internal("Failed to update user")?;
 
-    let round = rr
-        .get_active_round(&user)
+    let rounds = rr
+        .get_active_rounds(&user)
         .await
-        .map_user("No active game session")?;
+        .map_internal("Failed to find existing sessions")?;
+    let round = pick_session(rounds, session.as_deref())?;
+    round
+        .round
+        .party
+        .is_none()
+        .then_some(())
+        .map_user("Party rounds cannot be extended")?;
+
+    let time_limit = time_limit_for(&ar, &user, round.round.mode, round.round.round_no).await?;
+    let policy = CONFIG.modes.get(round.round.mode).extensions.as_ref();
+    let time_limit = match policy {
+        Some(policy) if !is_admin(&ctx, ctx.author()).await? => {
+            (round.attempt.extensions < policy.max)
+                .then_some(())
+                .map_user("No extensions left")?;
+            let current = round.attempt.state.until;
+            let max_until = round.attempt.created_at + policy.max_hold;
+            (current < max_until)
+                .then_some(())
+                .map_user("Maximum hold time reached")?;
+            let until = (current + policy.length(time_limit)).min(max_until);
+            until - Utc::now()
+        }
+        _ => time_limit,
+    };
 
     let round = ar
-        .extend_active_attempt(&user, round.round.mode.time_limit(round.round.round_no))
+        .extend_active_attempt(&user, &round.attempt.id, time_limit)
         .await
         .map_internal("Failed to extend timer")?;
 
@@ -0,0 +1,217 @@
+use crate::app::{
+    error::ConvertError, response::ResponseContext, stats_printer::StatsPrinter, AppContext,
+    AppError,
+};
+use gartic_on_demand::services::{
+    database::{
+        lobby::{Lobby, LobbyRepository},
+        stats::StatsRepository,
+        user::{User, UserRepository},
+        Record, ThingToU64,
+    },
+    provider::Provider,
+};
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+/// Manage private, invite-only lobbies
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("create", "invite", "kick", "status")
+)]
+pub async fn lobby(_ctx: AppContext<'_>) -> Result<(), AppError> {
+    Ok(())
+}
+
+/// Create a lobby you own
+#[poise::command(slash_command, guild_only)]
+async fn create(
+    ctx: AppContext<'_>,
+    #[description = "Lobby name"] name: String,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_create(&mut rsx, ctx, name).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Invite a player to your lobby
+#[poise::command(slash_command, guild_only)]
+async fn invite(
+    ctx: AppContext<'_>,
+    #[description = "Lobby name"] name: String,
+    #[description = "Player to invite"] player: serenity::User,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_invite(&mut rsx, ctx, name, player).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Remove a player from your lobby
+#[poise::command(slash_command, guild_only)]
+async fn kick(
+    ctx: AppContext<'_>,
+    #[description = "Lobby name"] name: String,
+    #[description = "Player to remove"] player: serenity::User,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_kick(&mut rsx, ctx, name, player).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Show members and incomplete games of a lobby
+#[poise::command(slash_command, guild_only)]
+async fn status(
+    ctx: AppContext<'_>,
+    #[description = "Lobby name"] name: String,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_status(&mut rsx, ctx, name).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn author(ctx: AppContext<'_>) -> Result<Record<User>, AppError> {
+    let ur: UserRepository = ctx.data().get();
+    let discord_user = ctx.author();
+    ur.create_or_update_user(discord_user.id.0, &discord_user.name)
+        .await
+        .map_internal("Failed to update user")
+}
+
+async fn owned_lobby(
+    lr: &LobbyRepository,
+    name: &str,
+    user: &Record<User>,
+) -> Result<Record<Lobby>, AppError> {
+    let lobby = lr.get(name).await.map_user("Unknown lobby")?;
+    (lobby.owner == user.id)
+        .then_some(lobby)
+        .map_user("Only the lobby owner can manage its members")
+}
+
+async fn process_create(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    name: String,
+) -> Result<(), AppError> {
+    let lr: LobbyRepository = ctx.data().get();
+    let user = author(ctx).await?;
+    lr.create(&name, &user)
+        .await
+        .map_user("Lobby already exists")?;
+    rsx.respond(|b| {
+        b.content(format!(
+            "Created lobby {}.\nUse `/lobby invite` to add players and `/start` with the lobby to play.",
+            name
+        ))
+    })
+    .await?;
+    Ok(())
+}
+
+async fn process_invite(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    name: String,
+    player: serenity::User,
+) -> Result<(), AppError> {
+    let lr: LobbyRepository = ctx.data().get();
+    let ur: UserRepository = ctx.data().get();
+    let user = author(ctx).await?;
+    let lobby = owned_lobby(&lr, &name, &user).await?;
+    let player = ur
+        .create_or_update_user(player.id.0, &player.name)
+        .await
+        .map_internal("Failed to update user")?;
+    lr.invite(&lobby, &player)
+        .await
+        .map_internal("Failed to invite player")?;
+    rsx.respond(|b| b.content(format!("Invited <@{}>", player.id())))
+        .await?;
+    Ok(())
+}
+
+async fn process_kick(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    name: String,
+    player: serenity::User,
+) -> Result<(), AppError> {
+    let lr: LobbyRepository = ctx.data().get();
+    let ur: UserRepository = ctx.data().get();
+    let user = author(ctx).await?;
+    let lobby = owned_lobby(&lr, &name, &user).await?;
+    let player = ur
+        .create_or_update_user(player.id.0, &player.name)
+        .await
+        .map_internal("Failed to update user")?;
+    lr.kick(&lobby, &player)
+        .await
+        .map_user("Player is not a removable member")?;
+    rsx.respond(|b| b.content(format!("Removed <@{}>", player.id())))
+        .await?;
+    Ok(())
+}
+
+async fn process_status(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    name: String,
+) -> Result<(), AppError> {
+    let lr: LobbyRepository = ctx.data().get();
+    let sr: StatsRepository = ctx.data().get();
+    let user = author(ctx).await?;
+    let lobby = lr.get(&name).await.map_user("Unknown lobby")?;
+    if !lobby.is_member(&user) {
+        None.map_user("You are not a member of this lobby")?;
+    }
+    let mut unallocated = sr
+        .get_unallocated_rounds(None, Some(&lobby.id))
+        .await
+        .map_internal("Failed to fetch incomplete games")?;
+    unallocated.retain(|u| u.round_no > 0 && u.unallocated > 0);
+    let members = lobby
+        .members
+        .iter()
+        .map(|member| format!("<@{}>", member.to_u64()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut games = unallocated
+        .iter()
+        .map(StatsPrinter::unallocated_round_to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if games.is_empty() {
+        games.push_str("None");
+    }
+    rsx.respond(|b| {
+        b.content(format!(
+            "Owner: <@{}>\nMembers: {}\nIncomplete games:\n{}",
+            lobby.owner.to_u64(),
+            members,
+            games
+        ))
+    })
+    .await?;
+    Ok(())
+}
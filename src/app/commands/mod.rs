@@ -1,11 +1,20 @@
 pub mod add_asset;
+pub mod admin;
+pub mod appeal;
 pub mod cancel;
 pub mod current;
 pub mod extend;
 pub mod help;
+pub mod lobby;
 pub mod notify;
+pub mod party;
+pub mod pause;
 pub mod purge;
 pub mod random_attributes;
+pub mod reliability;
 pub mod reroll;
+pub mod resume;
 pub mod start;
 pub mod submit;
+pub mod subscribe;
+pub mod theme;
@@ -0,0 +1,114 @@
+use crate::app::{
+    commands::start::{map_game, GameArg},
+    error::ConvertError,
+    response::ResponseContext,
+    util::dm_prompt,
+    AppContext, AppError,
+};
+use chrono::Duration;
+use gartic_on_demand::services::{
+    database::{
+        attempt::AttemptRepository, party::PartyRepository, round::RoundRepository,
+        user::UserRepository,
+    },
+    provider::Provider,
+    status_update::StatusUpdateWaker,
+};
+use poise::serenity_prelude::{utils::parse_username, UserId};
+use tracing::error;
+
+/// Play synchronously with a fixed group
+#[poise::command(slash_command, guild_only, subcommands("start"))]
+pub async fn party(_ctx: AppContext<'_>) -> Result<(), AppError> {
+    Ok(())
+}
+
+/// Start a party with one game per player, rotating them every round
+#[poise::command(slash_command, guild_only)]
+async fn start(
+    ctx: AppContext<'_>,
+    #[description = "Game mode you want to play"] mode: GameArg,
+    #[description = "Mentions of the other players"] players: String,
+    #[description = "Minutes per round, by default the mode's time limit"]
+    #[min = 1]
+    minutes: Option<i64>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process(&mut rsx, ctx, mode, players, minutes).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    mode: GameArg,
+    players: String,
+    minutes: Option<i64>,
+) -> Result<(), AppError> {
+    let ar: AttemptRepository = ctx.data().get();
+    let ur: UserRepository = ctx.data().get();
+    let rr: RoundRepository = ctx.data().get();
+    let pr: PartyRepository = ctx.data().get();
+    let mode = map_game(mode);
+
+    let mut ids = vec![ctx.author().id];
+    for mention in players.split_whitespace() {
+        let id = parse_username(mention)
+            .map(UserId)
+            .map_user("Players must be mentioned")?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.len() < 2 {
+        None.map_user("A party needs at least 2 players")?;
+    }
+
+    ar.expire_active_attempts()
+        .await
+        .map_internal("Failed to unlock expired sessions")?;
+    let mut users = Vec::with_capacity(ids.len());
+    for id in ids {
+        let discord_user = id.to_user(&ctx).await?;
+        let user = ur
+            .create_or_update_user(discord_user.id.0, &discord_user.name)
+            .await
+            .map_internal("Failed to update user")?;
+        let active = rr
+            .get_active_rounds(&user)
+            .await
+            .map_internal("Failed to find existing sessions")?;
+        if !active.is_empty() {
+            None.map_user("Every player has to finish their current game first")?;
+        }
+        users.push(user);
+    }
+
+    let created = pr
+        .create(mode, &users, minutes.map(Duration::minutes))
+        .await
+        .map_internal("Failed to create party")?;
+
+    let db = ctx.data().get();
+    for round in &created.rounds {
+        if let Err(e) = dm_prompt(ctx.serenity_context(), &db, round).await {
+            error!(error = %e, "Failed to send party prompt");
+        }
+    }
+
+    rsx.respond(|b| {
+        b.content(format!(
+            "Party started, prompts were sent in DMs.\nPlayers rotate <t:{}:R>.",
+            created.party.deadline.timestamp()
+        ))
+    })
+    .await?;
+    let waker: StatusUpdateWaker = ctx.data().get();
+    waker.wake();
+    Ok(())
+}
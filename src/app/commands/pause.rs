@@ -0,0 +1,77 @@
+use crate::app::{
+    config::CONFIG,
+    error::ConvertError,
+    response::ResponseContext,
+    util::{autocomplete_session, pick_session},
+    AppContext, AppError,
+};
+use gartic_on_demand::services::{
+    database::{attempt::AttemptRepository, round::RoundRepository, user::UserRepository},
+    provider::Provider,
+    status_update::StatusUpdateWaker,
+};
+use tracing::error;
+
+/// Pause the current game session, keeping its round for you
+#[poise::command(slash_command, guild_only)]
+pub async fn pause(
+    ctx: AppContext<'_>,
+    #[description = "Game session to pause"]
+    #[autocomplete = "autocomplete_session"]
+    session: Option<String>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process(&mut rsx, ctx, session).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    session: Option<String>,
+) -> Result<(), AppError> {
+    let ar: AttemptRepository = ctx.data().get();
+    let ur: UserRepository = ctx.data().get();
+    let rr: RoundRepository = ctx.data().get();
+    let user = ctx.author();
+    let user = ur
+        .create_or_update_user(user.id.0, &user.name)
+        .await
+        .map_internal("Failed to update user")?;
+
+    let rounds = rr
+        .get_active_rounds(&user)
+        .await
+        .map_internal("Failed to find existing sessions")?;
+    let round = pick_session(rounds, session.as_deref())?;
+    round
+        .round
+        .party
+        .is_none()
+        .then_some(())
+        .map_user("Party rounds cannot be paused")?;
+    (round.attempt.pauses < CONFIG.pauses.max_count)
+        .then_some(())
+        .map_user("No pauses left for this session")?;
+
+    let paused = ar
+        .pause_active_attempt(&user, &round.attempt, CONFIG.pauses.max_duration)
+        .await
+        .map_internal("Failed to pause session")?;
+    rsx.respond(|f| {
+        f.content(format!(
+            "Paused with {} minutes left.\nUse `/resume` before <t:{}:f> to keep your round.",
+            paused.state.remaining / 60,
+            paused.state.until.timestamp()
+        ))
+    })
+    .await?;
+    let waker: StatusUpdateWaker = ctx.data().get();
+    waker.wake();
+    Ok(())
+}
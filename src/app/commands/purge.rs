@@ -20,14 +20,14 @@ pub async fn purge(ctx: AppContext<'_>) -> Result<(), AppError> {
 async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(), AppError> {
     let user = ctx.author();
     has_admin(&ctx, user).await?;
-    let channels = &[
-        CONFIG.channels.complete,
-        CONFIG.channels.complete_nsfw,
-        CONFIG.channels.partial,
-        CONFIG.channels.partial_nsfw,
-        CONFIG.channels.moderation,
-        CONFIG.channels.rejects,
-    ];
+    let mut channels = vec![CONFIG.channels.moderation, CONFIG.channels.rejects];
+    for rating in CONFIG.ratings.iter() {
+        for channel in [rating.partial, rating.complete] {
+            if !channels.contains(&channel) {
+                channels.push(channel);
+            }
+        }
+    }
     let now = Utc::now();
     let message = format!(
         "Database was purged <t:{}>.\nAll images above are now unregistered.",
@@ -3,18 +3,18 @@ use crate::app::{
     error::ConvertError,
     rendering::ModeRenderer,
     response::ResponseContext,
-    util::{fetch_image_from_channel, image_to_attachment},
+    util::{fetch_image_from_channels, image_to_attachment},
     AppContext, AppError,
 };
 use gartic_on_demand::services::{
     database::{byproducts::ByproductsRepository, ThingToU64},
-    gamemodes::Mode,
-    image_processing::concat_2_2,
+    gamemodes::{ross::Ross, GameLogic, Mode},
+    image_processing::concat_grid,
     provider::Provider,
 };
 use tracing::error;
 
-/// Get 4 random attributes
+/// Get random attributes
 #[poise::command(slash_command, guild_only)]
 pub async fn random_attributes(ctx: AppContext<'_>) -> Result<(), AppError> {
     let mut rsx = ResponseContext::new(ctx);
@@ -29,7 +29,7 @@ pub async fn random_attributes(ctx: AppContext<'_>) -> Result<(), AppError> {
 
 async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(), AppError> {
     let br: ByproductsRepository = ctx.data().get();
-    let count = 4;
+    let count = Ross.last_round();
     let attempts = br
         .get_random_ross_attributes()
         .await
@@ -37,15 +37,15 @@ async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(
     if attempts.len() as u64 != count {
         return None.map_user("Not enough attributes");
     }
-    let mut images = Vec::with_capacity(4);
-    let mut authors = Vec::with_capacity(4);
+    let channels = CONFIG.ratings.partial_channels(0);
+    let mut images = Vec::with_capacity(count as usize);
+    let mut authors = Vec::with_capacity(count as usize);
     for attempt in attempts {
-        let image =
-            fetch_image_from_channel(&ctx, CONFIG.channels.partial, attempt.state.what).await?;
+        let image = fetch_image_from_channels(&ctx, &channels, attempt.state.what).await?;
         images.push(image);
         authors.push(attempt.who.to_u64());
     }
-    let image = concat_2_2(&images);
+    let image = concat_grid(&images);
     let attachment = image_to_attachment(image);
     rsx.purge().await?;
     let authors = Mode::Ross.render_partial_authors(&authors);
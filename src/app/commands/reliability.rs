@@ -0,0 +1,68 @@
+use crate::app::{
+    config::CONFIG, error::ConvertError, permission::has_mod, response::ResponseContext,
+    AppContext, AppError,
+};
+use gartic_on_demand::services::{
+    database::{attempt::AttemptRepository, user::UserRepository},
+    provider::Provider,
+};
+use poise::serenity_prelude::{self as serenity, Mentionable};
+use tracing::error;
+
+/// Show how often a player finishes the games they start
+#[poise::command(slash_command, guild_only)]
+pub async fn reliability(
+    ctx: AppContext<'_>,
+    #[description = "Player to look up"] player: serenity::User,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process(&mut rsx, ctx, player).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    player: serenity::User,
+) -> Result<(), AppError> {
+    has_mod(&ctx, ctx.author()).await?;
+    let Some(config) = &CONFIG.reliability else {
+        rsx.respond(|b| b.content("Reliability scoring is disabled"))
+            .await?;
+        return Ok(());
+    };
+    let ar: AttemptRepository = ctx.data().get();
+    let ur: UserRepository = ctx.data().get();
+    let user = ur
+        .create_or_update_user(player.id.0, &player.name)
+        .await
+        .map_internal("Failed to update user")?;
+
+    let record = ar
+        .get_reliability_record(&user, config.window)
+        .await
+        .map_internal("Failed to get reliability record")?;
+    let mut content = format!(
+        "{} scores {:.0}%: {} approved ({} late), {} cancelled and {} expired of their last {} games",
+        player.mention(),
+        record.score() * 100.0,
+        record.approved,
+        record.late,
+        record.cancelled,
+        record.expired,
+        record.total()
+    );
+    if config.is_unreliable(&record) {
+        content.push_str(&format!(
+            "\nTheir time limits are shortened to {:.0}%",
+            config.time_limit_factor * 100.0
+        ));
+    }
+    rsx.respond(|b| b.content(content)).await?;
+    Ok(())
+}
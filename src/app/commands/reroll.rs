@@ -1,9 +1,14 @@
 use crate::app::{
-    error::ConvertError, response::ResponseContext, util::respond_with_prompt, AppContext, AppError,
+    config::CONFIG,
+    error::ConvertError,
+    reliability::time_limit_for,
+    response::ResponseContext,
+    util::{autocomplete_session, pick_session, respond_with_prompt},
+    AppContext, AppError,
 };
+use chrono::Utc;
 use gartic_on_demand::services::{
     database::{attempt::AttemptRepository, round::RoundRepository, user::UserRepository},
-    gamemodes::GameLogic,
     provider::Provider,
     status_update::StatusUpdateWaker,
 };
@@ -11,10 +16,15 @@ use tracing::error;
 
 /// Reroll the current prompt
 #[poise::command(slash_command, guild_only)]
-pub async fn reroll(ctx: AppContext<'_>) -> Result<(), AppError> {
+pub async fn reroll(
+    ctx: AppContext<'_>,
+    #[description = "Game session to reroll"]
+    #[autocomplete = "autocomplete_session"]
+    session: Option<String>,
+) -> Result<(), AppError> {
     let mut rsx = ResponseContext::new(ctx);
     rsx.init().await?;
-    if let Err(e) = process(&mut rsx, ctx).await {
+    if let Err(e) = process(&mut rsx, ctx, session).await {
         error!(error = ?e);
         rsx.respond(|b| b.content(e.for_user())).await?
     }
@@ -22,7 +32,11 @@ pub async fn reroll(ctx: AppContext<'_>) -> Result<(), AppError> {
     Ok(())
 }
 
-async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(), AppError> {
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    session: Option<String>,
+) -> Result<(), AppError> {
     let ar: AttemptRepository = ctx.data().get();
     let ur: UserRepository = ctx.data().get();
     let rr: RoundRepository = ctx.data().get();
@@ -32,28 +46,36 @@ async fn process(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(
         .await
         .map_internal("Failed to update user")?;
 
-    let round = rr
-        .get_active_round(&user)
+    let rounds = rr
+        .get_active_rounds(&user)
         .await
-        .map_user("Failed to find existing session")?;
+        .map_internal("Failed to find existing sessions")?;
+    let round = pick_session(rounds, session.as_deref())?;
 
-    ar.cancel_active_attempt(&user)
+    let cancelled = ar
+        .reroll_active_attempt(&user, &round.attempt.id)
         .await
         .map_internal("Failed to cancel active session")?;
+    let skipped_since = (Utc::now() - CONFIG.skips.window).min(cancelled.state.when);
+    let time_limit = time_limit_for(&ar, &user, round.round.mode, round.round.round_no).await?;
 
     if let Ok(round) = rr
+        .with_allocation(CONFIG.modes.get(round.round.mode).allocation)
+        .with_skips_since(skipped_since)
         .attempt_existing_round(
             &user,
             round.round.mode,
-            round.round.nsfw,
+            round.round.rating,
+            round.round.lobby.as_ref(),
             round.round.round_no,
-            round.round.mode.time_limit(round.round.round_no),
+            time_limit,
+            &CONFIG.modes.get(round.round.mode).fairness,
         )
         .await
     {
         respond_with_prompt(rsx, &ctx, &round, false).await?;
     } else {
-        rsx.respond(|b| b.content("No rounds available currently.\nUse `/start` to play again."))
+        rsx.respond(|b| b.content("No rounds available currently.\nUse `/start` to play again or `/subscribe` to hear about new rounds."))
             .await?
     }
 
@@ -0,0 +1,66 @@
+use crate::app::{
+    config::CONFIG,
+    error::ConvertError,
+    response::ResponseContext,
+    util::{autocomplete_paused_session, pick_session, respond_with_prompt},
+    AppContext, AppError,
+};
+use gartic_on_demand::services::{
+    database::{attempt::AttemptRepository, round::RoundRepository, user::UserRepository},
+    provider::Provider,
+    status_update::StatusUpdateWaker,
+};
+use tracing::error;
+
+/// Resume a paused game session
+#[poise::command(slash_command, guild_only)]
+pub async fn resume(
+    ctx: AppContext<'_>,
+    #[description = "Paused game session to resume"]
+    #[autocomplete = "autocomplete_paused_session"]
+    session: Option<String>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process(&mut rsx, ctx, session).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    session: Option<String>,
+) -> Result<(), AppError> {
+    let ar: AttemptRepository = ctx.data().get();
+    let ur: UserRepository = ctx.data().get();
+    let rr: RoundRepository = ctx.data().get();
+    let user = ctx.author();
+    let user = ur
+        .create_or_update_user(user.id.0, &user.name)
+        .await
+        .map_internal("Failed to update user")?;
+
+    ar.expire_active_attempts()
+        .await
+        .map_internal("Failed to unlock expired sessions")?;
+    let paused = rr
+        .get_paused_rounds(&user)
+        .await
+        .map_internal("Failed to find paused sessions")?;
+    (!paused.is_empty())
+        .then_some(())
+        .map_user("No paused game session")?;
+    let paused = pick_session(paused, session.as_deref())?;
+    let round = ar
+        .resume_paused_attempt(&user, &paused.attempt.id, CONFIG.sessions.max_active)
+        .await
+        .map_user("Too many sessions in progress, or already playing this mode")?;
+    respond_with_prompt(rsx, &ctx, &round, false).await?;
+    let waker: StatusUpdateWaker = ctx.data().get();
+    waker.wake();
+    Ok(())
+}
@@ -1,13 +1,17 @@
 use crate::app::{
+    config::CONFIG,
     error::{AppError, ConvertError},
-    permission::is_adult,
+    permission::can_play_rating,
+    reliability::time_limit_for,
     response::ResponseContext,
     util::respond_with_prompt,
     AppContext,
 };
+use chrono::Utc;
 use gartic_on_demand::services::{
     database::{
         attempt::{Active, AttemptRepository},
+        lobby::LobbyRepository,
         round::{RoundRepository, RoundWithAttempts},
         user::{User, UserRepository},
         DbError, Record,
@@ -17,6 +21,7 @@ use gartic_on_demand::services::{
     status_update::StatusUpdateWaker,
 };
 use std::ops::Sub;
+use surrealdb::sql::Thing;
 use tracing::error;
 
 #[derive(Debug, poise::ChoiceParameter)]
@@ -30,12 +35,13 @@ pub enum GameArg {
 pub async fn start(
     ctx: AppContext<'_>,
     #[description = "Game mode you want to play"] mode: GameArg,
-    #[description = "Play the NSFW variant (+18 only)"] nsfw: Option<bool>,
+    #[description = "Most explicit rating to play"] rating: Option<String>,
+    #[description = "Play in a private lobby you are a member of"] lobby: Option<String>,
     //#[description = "Round to start at"]#[min = 1] round: Option<u64>,
 ) -> Result<(), AppError> {
     let mut rsx = ResponseContext::new(ctx);
     rsx.init().await?;
-    if let Err(e) = process(&mut rsx, ctx, mode, None, nsfw).await {
+    if let Err(e) = process(&mut rsx, ctx, mode, None, rating, lobby).await {
         error!(error = ?e);
         rsx.respond(|b| b.content(e.for_user())).await?
     }
@@ -48,19 +54,29 @@ async fn process(
     ctx: AppContext<'_>,
     mode: GameArg,
     round: Option<u64>,
-    nsfw: Option<bool>,
+    rating: Option<String>,
+    lobby: Option<String>,
 ) -> Result<(), AppError> {
     let round_no = round.unwrap_or(1).sub(1);
-    let nsfw = nsfw.unwrap_or(false);
+    let rating = match rating {
+        Some(name) => CONFIG.ratings.level(&name).map_user("Unknown rating")?,
+        None => 0,
+    };
 
     let ar: AttemptRepository = ctx.data().get();
     let ur: UserRepository = ctx.data().get();
     let rr: RoundRepository = ctx.data().get();
+    let lr: LobbyRepository = ctx.data().get();
     let user = ctx.author();
 
-    if nsfw && !is_adult(&ctx, user).await? {
-        rsx.respond(|b| b.content("You need the `+18` role to participate in NSFW games."))
-            .await?;
+    if !can_play_rating(&ctx, user, rating).await? {
+        rsx.respond(|b| {
+            b.content(format!(
+                "You need the roles of every rating up to {} to participate in these games.",
+                CONFIG.ratings.get(rating).name
+            ))
+        })
+        .await?;
         return Ok(());
     }
 
@@ -69,19 +85,60 @@ async fn process(
         .await
         .map_internal("Failed to update user")?;
 
+    let lobby = match lobby {
+        Some(name) => {
+            let lobby = lr.get(&name).await.map_user("Unknown lobby")?;
+            if !lobby.is_member(&user) {
+                None.map_user("You are not a member of this lobby")?;
+            }
+            Some(lobby.id.clone())
+        }
+        None => None,
+    };
+
     ar.expire_active_attempts()
         .await
         .map_internal("Failed to unlock expired sessions")?;
     let waker: StatusUpdateWaker = ctx.data().get();
     waker.wake();
 
-    let maybe_lobby = rr.get_active_round(&user).await;
-    if let Ok(lobby) = maybe_lobby {
-        return respond_with_prompt(rsx, &ctx, &lobby, true).await;
+    let mode = map_game(mode);
+    let active = rr
+        .get_active_rounds(&user)
+        .await
+        .map_internal("Failed to find existing sessions")?;
+    let paused = rr
+        .get_paused_rounds(&user)
+        .await
+        .map_internal("Failed to find paused sessions")?;
+    if let Some(existing) = active.iter().find(|r| r.round.mode == mode) {
+        return respond_with_prompt(rsx, &ctx, existing, true).await;
+    }
+    if paused.iter().any(|r| r.round.mode == mode) {
+        rsx.respond(|b| {
+            b.content(format!(
+                "Your {:?} mode game is paused, `/resume` it to continue.",
+                mode
+            ))
+        })
+        .await?;
+        return Ok(());
+    }
+    let sessions = active.len() + paused.len();
+    if sessions as u64 >= CONFIG.sessions.max_active {
+        rsx.respond(|b| {
+            b.content(format!(
+                "You already play {} games, finish or `/cancel` one first.",
+                sessions
+            ))
+        })
+        .await?;
+        return Ok(());
     }
 
-    let lobby = find_or_create_session(rr, &user, mode, round_no, nsfw).await?;
-    respond_with_prompt(rsx, &ctx, &lobby, false).await?;
+    let round =
+        find_or_create_session(rr, &ar, &user, mode, round_no, rating, lobby.as_ref()).await?;
+    respond_with_prompt(rsx, &ctx, &round, false).await?;
     let waker: StatusUpdateWaker = ctx.data().get();
     waker.wake();
     Ok(())
@@ -89,35 +146,42 @@ async fn process(
 
 async fn find_or_create_session(
     rr: RoundRepository,
+    ar: &AttemptRepository,
     user: &Record<User>,
-    mode: GameArg,
+    mode: Mode,
     round_no: u64,
-    nsfw: bool,
+    rating: u64,
+    lobby: Option<&Thing>,
 ) -> Result<RoundWithAttempts<Active>, AppError> {
-    let mode = map_game(mode);
-
     if round_no > mode.last_round() {
         None.map_user("Gamemode does not support this many rounds")?;
     }
 
-    let time_limit = mode.time_limit(round_no);
+    let time_limit = time_limit_for(ar, user, mode, round_no).await?;
+    let rr = rr
+        .with_allocation(CONFIG.modes.get(mode).allocation)
+        .with_skips_since(Utc::now() - CONFIG.skips.window);
 
-    let maybe_lobby = rr
-        .attempt_existing_round(user, mode, nsfw, round_no, time_limit)
+    let fairness = &CONFIG.modes.get(mode).fairness;
+    let existing = rr
+        .attempt_existing_round(user, mode, rating, lobby, round_no, time_limit, fairness)
         .await;
-    let round = match (maybe_lobby, round_no) {
-        (Ok(lobby), _) => lobby,
-        (Err(DbError::NotFound), 0) => rr
-            .attempt_new_round(user, mode, nsfw, mode.multiplex(round_no), time_limit)
-            .await
-            .map_internal("Failed to create game session")?,
-        (Err(e), _) => Err(e).map_user("Did not find pending sessions")?,
+    let round = match (existing, round_no) {
+        (Ok(round), _) => round,
+        (Err(DbError::NotFound), 0) => {
+            let multiplex = CONFIG.modes.multiplex(mode, round_no);
+            rr.attempt_new_round(user, mode, rating, lobby, multiplex, time_limit)
+                .await
+                .map_internal("Failed to create game session")?
+        }
+        (Err(e), _) => Err(e)
+            .map_user("Did not find pending sessions, use `/subscribe` to hear about new rounds")?,
     };
 
     Ok(round)
 }
 
-fn map_game(mode: GameArg) -> Mode {
+pub fn map_game(mode: GameArg) -> Mode {
     match mode {
         GameArg::Ross => Mode::Ross,
         GameArg::Evolution => Mode::Evolution,
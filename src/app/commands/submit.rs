@@ -2,11 +2,16 @@ use crate::app::{
     config::CONFIG,
     error::ConvertError,
     permission::is_trusted,
+    reliability::time_limit_for,
     rendering::{ModeRenderer, RoundRenderer},
     response::ResponseContext,
-    util::respond_with_prompt,
+    util::{
+        autocomplete_submission, forward_round, pick_session, respond_with_prompt, reveal_partials,
+        spoiler,
+    },
     AppContext, AppError,
 };
+use chrono::Utc;
 use gartic_on_demand::services::{
     database::{attempt::AttemptRepository, round::RoundRepository, user::UserRepository},
     gamemodes::GameLogic,
@@ -21,10 +26,13 @@ use tracing::error;
 pub async fn submit(
     ctx: AppContext<'_>,
     #[description = "Your submission"] attachment: Attachment,
+    #[description = "Game session to submit to"]
+    #[autocomplete = "autocomplete_submission"]
+    session: Option<String>,
 ) -> Result<(), AppError> {
     let mut rsx = ResponseContext::new(ctx);
     rsx.init().await?;
-    if let Err(e) = process(&mut rsx, ctx, attachment).await {
+    if let Err(e) = process(&mut rsx, ctx, attachment, session).await {
         error!(error = ?e);
         rsx.respond(|b| b.content(e.for_user())).await?
     }
@@ -36,6 +44,7 @@ async fn process(
     rsx: &mut ResponseContext<'_>,
     ctx: AppContext<'_>,
     attachment: Attachment,
+    session: Option<String>,
 ) -> Result<(), AppError> {
     let ar: AttemptRepository = ctx.data().get();
     let ur: UserRepository = ctx.data().get();
@@ -46,60 +55,109 @@ async fn process(
         .await
         .map_internal("Failed to update user")?;
 
-    let round = ar
-        .upload_active_attempt(&user)
+    let rounds = rr
+        .get_active_rounds(&user)
         .await
-        .map_internal("Failed to find existing session")?;
+        .map_internal("Failed to find existing sessions")?;
+    let since = Utc::now() - CONFIG.late_submissions.grace;
+    let late = rr
+        .get_late_rounds(&user, since)
+        .await
+        .map_internal("Failed to find existing sessions")?;
+    (session.is_some() || rounds.len() + late.len() <= 1)
+        .then_some(())
+        .map_user("You have several game sessions, pick one with the `session` option")?;
+    let late = match session.as_deref() {
+        Some(session) => late
+            .into_iter()
+            .find(|r| r.attempt.id.id.to_raw() == session),
+        None => late.into_iter().next(),
+    };
+    let round = match late {
+        Some(late) => ar
+            .upload_late_attempt(&user, &late.attempt.id, since)
+            .await
+            .map_internal("Failed to find existing session")?,
+        None => {
+            let round = pick_session(rounds, session.as_deref())?;
+            ar.upload_active_attempt(&user, &round.attempt.id)
+                .await
+                .map_internal("Failed to find existing session")?
+        }
+    };
 
-    let trusted = is_trusted(&ctx, discord_user).await?;
+    let trusted = is_trusted(&ctx, discord_user).await?
+        && !CONFIG.spot_checks.sample(
+            round.round.mode,
+            round.round.round_no,
+            CONFIG.ratings.get(round.round.rating),
+        );
 
     if trusted {
-        let (channel, attachment, content) =
-            if round.round.round_no == round.round.mode.last_round() {
-                let channel = match round.round.nsfw {
-                    true => CONFIG.channels.complete_nsfw,
-                    false => CONFIG.channels.complete,
-                };
-                let attachment = round
-                    .round
-                    .mode
-                    .render_complete_image(&ctx, &round, &ctx.data().get(), &attachment)
-                    .await?;
-                let content = round.render_complete_text();
-                (channel, attachment, content)
-            } else {
-                let channel = match round.round.nsfw {
-                    true => CONFIG.channels.partial_nsfw,
-                    false => CONFIG.channels.partial,
-                };
-                let attachment = round.round.mode.render_partial_image(&attachment).await?;
-                let content = round.render_partial_text();
-                (channel, attachment, content)
-            };
+        let (channel, attachment, content) = if round.round.round_no
+            == round.round.mode.last_round()
+        {
+            let channel = CONFIG.ratings.get(round.round.rating).complete;
+            let attachment = round
+                .round
+                .mode
+                .render_complete_image(&ctx, &round, &ctx.data().get(), &attachment)
+                .await?;
+            let attachment = spoiler(attachment, CONFIG.ratings.get(round.round.rating).spoiler);
+            let content = round.render_complete_text();
+            (channel, attachment, content)
+        } else {
+            let channel = CONFIG.ratings.get(round.round.rating).partial;
+            let attachment = round
+                .round
+                .mode
+                .render_partial_image(round.round.round_no, &attachment)
+                .await?;
+            let attachment = spoiler(attachment, CONFIG.spoilers.partial);
+            let content = round.render_public_partial_text();
+            (channel, attachment, content)
+        };
 
         let message = channel
-            .send_message(ctx, |m| m.add_file(attachment).content(content))
+            .send_message(ctx, |m| {
+                m.add_file(attachment)
+                    .content(content)
+                    .reactions([ReactionType::Unicode(CONFIG.reactions.report.clone())])
+            })
             .await?;
         let round = ar
-            .approve_uploaded_attempt(&user, message.id.0)
+            .approve_uploaded_attempt(&user, &round.attempt.id, message.id.0)
             .await
             .map_internal("Failed to attach image")?;
-        rr.forward_complete_round(&round.round, &round.attempt, round.round.forward())
-            .await
-            .map_internal("Failed to forward round")?;
+        forward_round(ctx.serenity_context(), &ctx.data().get(), &round).await?;
+        if round.round.round_no == round.round.mode.last_round() {
+            reveal_partials(&ctx.data().get(), &round).await?;
+        }
     } else {
         let channel = CONFIG.channels.moderation;
-        let attachment = round.round.mode.render_partial_image(&attachment).await?;
+        let attachment = round
+            .round
+            .mode
+            .render_partial_image(round.round.round_no, &attachment)
+            .await?;
         let content = round.render_partial_text();
         let message = channel
             .send_message(ctx, |m| {
-                m.add_file(attachment).content(content).reactions([
-                    ReactionType::Unicode(CONFIG.reactions.accept.clone()),
-                    ReactionType::Unicode(CONFIG.reactions.reject.clone()),
-                ])
+                let ratings = CONFIG
+                    .ratings
+                    .iter()
+                    .map(|r| ReactionType::Unicode(r.reaction.clone()));
+                m.add_file(attachment).content(content).reactions(
+                    [
+                        ReactionType::Unicode(CONFIG.reactions.accept.clone()),
+                        ReactionType::Unicode(CONFIG.reactions.reject.clone()),
+                    ]
+                    .into_iter()
+                    .chain(ratings),
+                )
             })
             .await?;
-        ar.moderate_uploaded_attempt(&user, message.id.0)
+        ar.moderate_uploaded_attempt(&user, &round.attempt.id, message.id.0)
             .await
             .map_internal("Failed to attach image")?;
     }
@@ -110,18 +168,33 @@ async fn process(
     if round.round.round_no == round.round.mode.last_round() {
         rsx.respond(|b| b.content("This was the final round.\nUse `/start` to play again."))
             .await?;
+    } else if round.round.party.is_some() {
+        rsx.respond(|b| b.content("Your next prompt will arrive in DMs once the round ends."))
+            .await?;
     } else {
         let round_no = round.round.round_no + 1;
         let mode = round.round.mode;
-        let nsfw = round.round.nsfw;
+        let rating = round.round.rating;
+        let lobby = round.round.lobby.as_ref();
+        let time_limit = time_limit_for(&ar, &user, mode, round_no).await?;
         if let Ok(round) = rr
-            .attempt_existing_round(&user, mode, nsfw, round_no, mode.time_limit(round_no))
+            .with_allocation(CONFIG.modes.get(mode).allocation)
+            .with_skips_since(Utc::now() - CONFIG.skips.window)
+            .attempt_existing_round(
+                &user,
+                mode,
+                rating,
+                lobby,
+                round_no,
+                time_limit,
+                &CONFIG.modes.get(mode).fairness,
+            )
             .await
         {
             respond_with_prompt(rsx, &ctx, &round, false).await?;
         } else {
             rsx.respond(|b| {
-                b.content("No further rounds available currently.\nUse `/start` to play again.")
+                b.content("No further rounds available currently.\nUse `/start` to play again or `/subscribe` to hear about new rounds.")
             })
             .await?
         }
@@ -0,0 +1,90 @@
+use super::start::{map_game, GameArg};
+use crate::app::{
+    config::CONFIG, error::ConvertError, permission::can_play_rating, response::ResponseContext,
+    AppContext, AppError,
+};
+use gartic_on_demand::services::{
+    database::{subscription::SubscriptionRepository, user::UserRepository},
+    gamemodes::GameLogic,
+    provider::Provider,
+};
+use tracing::error;
+
+/// Get a DM once a game continues, instead of checking back with /start
+#[poise::command(slash_command, guild_only)]
+pub async fn subscribe(
+    ctx: AppContext<'_>,
+    #[description = "Game mode you want to play"] mode: GameArg,
+    #[description = "Round to wait for, any by default"]
+    #[min = 2]
+    round: Option<u64>,
+    #[description = "Most explicit rating to play"] rating: Option<String>,
+    #[description = "Join the round right away and DM the prompt"] auto_claim: Option<bool>,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process(&mut rsx, ctx, mode, round, rating, auto_claim).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn process(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    mode: GameArg,
+    round: Option<u64>,
+    rating: Option<String>,
+    auto_claim: Option<bool>,
+) -> Result<(), AppError> {
+    let mode = map_game(mode);
+    let round_no = round.map(|r| r - 1);
+    if round_no.is_some_and(|r| r > mode.last_round()) {
+        None.map_user("Gamemode does not support this many rounds")?;
+    }
+    let rating = match rating {
+        Some(name) => CONFIG.ratings.level(&name).map_user("Unknown rating")?,
+        None => 0,
+    };
+    let discord_user = ctx.author();
+    if !can_play_rating(&ctx, discord_user, rating).await? {
+        rsx.respond(|b| {
+            b.content(format!(
+                "You need the roles of every rating up to {} to participate in these games.",
+                CONFIG.ratings.get(rating).name
+            ))
+        })
+        .await?;
+        return Ok(());
+    }
+
+    let ur: UserRepository = ctx.data().get();
+    let sr: SubscriptionRepository = ctx.data().get();
+    let user = ur
+        .create_or_update_user(discord_user.id.0, &discord_user.name)
+        .await
+        .map_internal("Failed to update user")?;
+    let subscription = sr
+        .subscribe(
+            &user,
+            mode,
+            round_no,
+            rating,
+            auto_claim.unwrap_or(false),
+            CONFIG.subscriptions.ttl,
+        )
+        .await
+        .map_internal("Failed to subscribe")?;
+
+    rsx.respond(|b| {
+        b.content(format!(
+            "You will get a DM for the next {:?} mode round until <t:{}:f>.",
+            mode,
+            subscription.expires_at.timestamp()
+        ))
+    })
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use crate::app::{
+    error::ConvertError, permission::has_admin, response::ResponseContext,
+    stats_printer::StatsPrinter, AppContext, AppError,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use gartic_on_demand::services::{
+    database::{
+        stats::StatsRepository,
+        theme::{Theme, ThemeRepository},
+    },
+    provider::Provider,
+};
+use tracing::error;
+
+/// Manage seasonal themes
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("create", "remove", "list", "status")
+)]
+pub async fn theme(_ctx: AppContext<'_>) -> Result<(), AppError> {
+    Ok(())
+}
+
+/// Create a theme active between two dates
+#[poise::command(slash_command, guild_only)]
+async fn create(
+    ctx: AppContext<'_>,
+    #[description = "Theme name"] name: String,
+    #[description = "First day of the theme (YYYY-MM-DD)"] starts: String,
+    #[description = "Last day of the theme (YYYY-MM-DD)"] ends: String,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_create(&mut rsx, ctx, name, starts, ends).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Remove a theme
+#[poise::command(slash_command, guild_only)]
+async fn remove(
+    ctx: AppContext<'_>,
+    #[description = "Theme name"] name: String,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_remove(&mut rsx, ctx, name).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// List all themes
+#[poise::command(slash_command, guild_only)]
+async fn list(ctx: AppContext<'_>) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_list(&mut rsx, ctx).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+/// Show incomplete games of a theme
+#[poise::command(slash_command, guild_only)]
+async fn status(
+    ctx: AppContext<'_>,
+    #[description = "Theme name"] name: String,
+) -> Result<(), AppError> {
+    let mut rsx = ResponseContext::new(ctx);
+    rsx.init().await?;
+    if let Err(e) = process_status(&mut rsx, ctx, name).await {
+        error!(error = ?e);
+        rsx.respond(|b| b.content(e.for_user())).await?
+    }
+    rsx.finalize().await?;
+    Ok(())
+}
+
+async fn process_create(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    name: String,
+    starts: String,
+    ends: String,
+) -> Result<(), AppError> {
+    has_admin(&ctx, ctx.author()).await?;
+    let tr: ThemeRepository = ctx.data().get();
+    let starts_at = parse_day(&starts)?;
+    let ends_at = parse_day(&ends)? + Duration::days(1);
+    if ends_at <= starts_at {
+        None.map_user("Theme must end after it starts")?;
+    }
+    tr.create(Theme {
+        name,
+        starts_at,
+        ends_at,
+    })
+    .await
+    .map_user("Theme already exists")?;
+    rsx.respond(|b| b.content("Created")).await?;
+    Ok(())
+}
+
+async fn process_remove(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    name: String,
+) -> Result<(), AppError> {
+    has_admin(&ctx, ctx.author()).await?;
+    let tr: ThemeRepository = ctx.data().get();
+    tr.delete(&name).await.map_user("Unknown theme")?;
+    rsx.respond(|b| b.content("Removed")).await?;
+    Ok(())
+}
+
+async fn process_list(rsx: &mut ResponseContext<'_>, ctx: AppContext<'_>) -> Result<(), AppError> {
+    let tr: ThemeRepository = ctx.data().get();
+    let themes = tr.get_all().await.map_internal("Failed to fetch themes")?;
+    let mut content = themes
+        .iter()
+        .map(|theme| {
+            format!(
+                "- {} <t:{}:d> - <t:{}:d>",
+                theme.name,
+                theme.starts_at.timestamp(),
+                theme.ends_at.timestamp()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if content.is_empty() {
+        content.push_str("None");
+    }
+    rsx.respond(|b| b.content(content)).await?;
+    Ok(())
+}
+
+async fn process_status(
+    rsx: &mut ResponseContext<'_>,
+    ctx: AppContext<'_>,
+    name: String,
+) -> Result<(), AppError> {
+    let sr: StatsRepository = ctx.data().get();
+    let mut unallocated = sr
+        .get_unallocated_rounds(Some(&name), None)
+        .await
+        .map_internal("Failed to fetch incomplete games")?;
+    unallocated.retain(|u| u.round_no > 0 && u.unallocated > 0);
+    let mut content = unallocated
+        .iter()
+        .map(StatsPrinter::unallocated_round_to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if content.is_empty() {
+        content.push_str("None");
+    }
+    rsx.respond(|b| b.content(content)).await?;
+    Ok(())
+}
+
+fn parse_day(day: &str) -> Result<DateTime<Utc>, AppError> {
+    let day =
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").map_user("Invalid date, expected YYYY-MM-DD")?;
+    Ok(day.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
@@ -1,12 +1,25 @@
 use crate::app::log::LogConfig;
+use chrono::Duration;
 use config::{Config, Environment, File, FileFormat};
 use dotenv::dotenv;
-use gartic_on_demand::services::database::DatabaseConfig;
+use gartic_on_demand::services::{
+    database::{
+        round::{AllocationStrategy, Fairness},
+        DatabaseConfig,
+    },
+    gamemodes::{ross::Ross, GameLogic, Mode},
+};
 use lazy_static::lazy_static;
 use serde::{de::DeserializeOwned, Deserialize};
-use serenity::model::prelude::{ChannelId, GuildId, RoleId};
+use serde_with::{serde_as, DurationSeconds};
+use serenity::model::prelude::{ChannelId, GuildId, ReactionType, RoleId};
+use std::cmp::Ordering;
 
-use super::{expiry_notifier::ExpiryNotifierConfig, stats_printer::StatsPrinterConfig};
+use super::{
+    expiry_notifier::ExpiryNotifierConfig, party_runner::PartyRunnerConfig,
+    reliability::ReliabilityConfig, spoiler_revealer::SpoilerRevealerConfig,
+    stats_printer::StatsPrinterConfig, trust::TrustConfig, vote_closer::VoteCloserConfig,
+};
 
 lazy_static! {
     pub static ref CONFIG: AppConfig = init();
@@ -30,11 +43,25 @@ pub struct AppConfig {
     pub channels: Channels,
     pub roles: Roles,
     pub reactions: Reactions,
-    pub image: Image,
+    pub modes: Modes,
+    pub spoilers: Spoilers,
+    pub spot_checks: SpotChecks,
+    pub appeals: Appeals,
+    pub late_submissions: LateSubmissions,
+    pub pauses: Pauses,
+    pub sessions: Sessions,
+    pub skips: Skips,
+    pub subscriptions: Subscriptions,
+    pub ratings: Ratings,
     pub log: LogConfig,
     pub database: DatabaseConfig,
     pub stats_printer: StatsPrinterConfig,
     pub expiry_notifier: ExpiryNotifierConfig,
+    pub vote_closer: VoteCloserConfig,
+    pub party_runner: PartyRunnerConfig,
+    pub spoiler_revealer: SpoilerRevealerConfig,
+    pub trust: Option<TrustConfig>,
+    pub reliability: Option<ReliabilityConfig>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,11 +69,8 @@ pub struct Channels {
     pub draw_this: ChannelId,
     pub in_contruction: ChannelId,
     pub moderation: ChannelId,
-    pub partial: ChannelId,
-    pub complete: ChannelId,
-    pub partial_nsfw: ChannelId,
-    pub complete_nsfw: ChannelId,
     pub rejects: ChannelId,
+    pub appeals: ChannelId,
     pub stats: ChannelId,
 }
 
@@ -55,7 +79,6 @@ pub struct Roles {
     pub admin: RoleId,
     pub moderator: RoleId,
     pub trusted: RoleId,
-    pub adult: RoleId,
     pub notify_always: RoleId,
 }
 
@@ -65,10 +88,248 @@ pub struct Reactions {
     pub reject: String,
     pub reroll: String,
     pub delete: String,
+    pub vote: String,
+    pub report: String,
+    pub takedown: String,
+    pub dismiss: String,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct Spoilers {
+    pub partial: bool,
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    pub reveal_after: Option<std::time::Duration>,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct Appeals {
+    /// How long after a rejection it can still be appealed.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub window: Duration,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct LateSubmissions {
+    /// How long after expiring an attempt still accepts a submission, unless its round was claimed.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub grace: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Sessions {
+    /// Active attempts a player can hold at once, at most one per mode.
+    pub max_active: u64,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct Skips {
+    /// How long rounds a player cancelled, rerolled or let expire are not offered to them again, along with the rest of their chains.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub window: Duration,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct Subscriptions {
+    /// How long a subscription waits for a matching round.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub ttl: Duration,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct Pauses {
+    /// How long a paused attempt keeps its round before expiring.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub max_duration: Duration,
+    pub max_count: u64,
+}
+
+/// Trusted submissions routed through moderation anyway.
+#[derive(Debug, Deserialize)]
+pub struct SpotChecks {
+    /// Chance between 0 and 1 of sampling any trusted submission.
+    pub rate: f64,
+    pub final_rounds: bool,
+}
+
+impl SpotChecks {
+    pub fn sample(&self, mode: Mode, round_no: u64, rating: &Rating) -> bool {
+        rating.always_moderate
+            || (self.final_rounds && round_no == mode.last_round())
+            || rand::random::<f64>() < self.rate
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Rating {
+    pub name: String,
+    pub role: Option<RoleId>,
+    pub partial: ChannelId,
+    pub complete: ChannelId,
+    pub spoiler: bool,
+    pub reaction: String,
+    pub always_moderate: bool,
 }
 
+/// Content ratings ordered from the mildest, rounds and assets store the level of their rating.
 #[derive(Debug, Deserialize)]
-pub struct Image {
-    pub width: u32,
-    pub height: u32,
+#[serde(try_from = "Vec<Rating>")]
+pub struct Ratings {
+    ladder: Vec<Rating>,
+}
+
+impl TryFrom<Vec<Rating>> for Ratings {
+    type Error = &'static str;
+
+    fn try_from(ladder: Vec<Rating>) -> Result<Self, Self::Error> {
+        if ladder.is_empty() {
+            return Err("ratings need at least one rating");
+        }
+        Ok(Self { ladder })
+    }
+}
+
+impl Ratings {
+    /// Levels past the end of the ladder fall back to its top rating.
+    pub fn get(&self, level: u64) -> &Rating {
+        let level = (level as usize).min(self.ladder.len() - 1);
+        &self.ladder[level]
+    }
+
+    pub fn level(&self, name: &str) -> Option<u64> {
+        self.ladder
+            .iter()
+            .position(|r| r.name.eq_ignore_ascii_case(name))
+            .map(|level| level as u64)
+    }
+
+    pub fn level_of_reaction(&self, emoji: &ReactionType) -> Option<u64> {
+        self.ladder
+            .iter()
+            .position(|r| emoji.unicode_partial_cmp(&r.reaction) == Some(Ordering::Equal))
+            .map(|level| level as u64)
+    }
+
+    /// Ratings up to and including `level`.
+    pub fn up_to(&self, level: u64) -> impl Iterator<Item = &Rating> {
+        self.ladder.iter().take(level as usize + 1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rating> {
+        self.ladder.iter()
+    }
+
+    /// Partial channels which may hold images of a chain rated `level`, its own channel first.
+    /// Chains re-rated during review keep their earlier images in the channels of their former rating.
+    pub fn partial_channels(&self, level: u64) -> Vec<ChannelId> {
+        let mut channels = vec![self.get(level).partial];
+        for rating in &self.ladder {
+            if !channels.contains(&rating.partial) {
+                channels.push(rating.partial);
+            }
+        }
+        channels
+    }
+
+    /// Prefix naming the rating in messages, empty for the mildest rating.
+    pub fn prefix(&self, level: u64) -> String {
+        match level {
+            0 => String::new(),
+            level => format!("{} ", self.get(level).name),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Modes {
+    pub ross: ModeConfig,
+    pub evolution: ModeConfig,
+}
+
+impl Modes {
+    pub fn get(&self, mode: Mode) -> &ModeConfig {
+        match mode {
+            Mode::Ross => &self.ross,
+            Mode::Evolution => &self.evolution,
+        }
+    }
+
+    /// Competition settings of a round, final rounds are never competitive.
+    pub fn competitive(&self, mode: Mode, round_no: u64) -> Option<&CompetitiveConfig> {
+        self.get(mode)
+            .competitive
+            .as_ref()
+            .filter(|_| round_no < mode.last_round())
+    }
+
+    pub fn multiplex(&self, mode: Mode, round_no: u64) -> u64 {
+        match self.competitive(mode, round_no) {
+            Some(competitive) => competitive.drafts,
+            None => mode.multiplex(round_no),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModeConfig {
+    pub captions: bool,
+    pub anonymous: bool,
+    pub competitive: Option<CompetitiveConfig>,
+    pub extensions: Option<ExtensionPolicy>,
+    #[serde(default)]
+    pub fairness: Fairness,
+    #[serde(default)]
+    pub allocation: AllocationStrategy,
+    /// Number of attribute rounds, only read for Ross.
+    /// Changing it while Ross chains are unfinished changes which of their rounds is final.
+    pub attributes: Option<Attributes>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(try_from = "u64")]
+pub struct Attributes(pub u64);
+
+impl TryFrom<u64> for Attributes {
+    type Error = String;
+
+    fn try_from(count: u64) -> Result<Self, Self::Error> {
+        match Ross::SUPPORTED_ATTRIBUTES.contains(&count) {
+            true => Ok(Self(count)),
+            false => Err(format!(
+                "unsupported attribute count {count}, expected one of {:?}",
+                Ross::SUPPORTED_ATTRIBUTES
+            )),
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct CompetitiveConfig {
+    pub drafts: u64,
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub voting: Duration,
+}
+
+/// Limits on `/extend`, modes without a policy reset the timer to the full time limit.
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct ExtensionPolicy {
+    pub max: u64,
+    /// Every extension pushes the current expiry back by this fraction of the time limit.
+    pub fraction: f64,
+    /// Total time an attempt can be held, counted from its start.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub max_hold: Duration,
+}
+
+impl ExtensionPolicy {
+    pub fn length(&self, time_limit: Duration) -> Duration {
+        Duration::milliseconds((time_limit.num_milliseconds() as f64 * self.fraction) as i64)
+    }
 }
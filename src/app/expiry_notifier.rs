@@ -1,7 +1,7 @@
 use crate::app::{config::CONFIG, error::ConvertError};
 
 use super::error::AppError;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{Duration, Utc};
 use gartic_on_demand::services::{
     database::{attempt::AttemptRepository, Database, ThingToU64},
     provider::Provider,
@@ -10,15 +10,16 @@ use poise::serenity_prelude::UserId;
 use serde::Deserialize;
 use serde_with::{serde_as, DurationSeconds};
 use serenity::prelude::Context;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[serde_as]
 #[derive(Debug, Deserialize)]
 pub struct ExpiryNotifierConfig {
     #[serde_as(as = "DurationSeconds<u64>")]
     check_interval: std::time::Duration,
-    #[serde_as(as = "DurationSeconds<i64>")]
-    in_advance: Duration,
+    /// How long before expiry users are reminded, each offset reminds once.
+    #[serde_as(as = "Vec<DurationSeconds<i64>>")]
+    reminders: Vec<Duration>,
 }
 
 pub struct ExpiryNotifier {
@@ -41,37 +42,47 @@ impl ExpiryNotifier {
 
     pub async fn run_internal(&mut self) -> Result<(), AppError> {
         info!("Starting expiry notifier");
-        let mut last_check = Utc::now();
+        let mut reminders = CONFIG.expiry_notifier.reminders.clone();
+        // Offsets closest to expiry go first, so one reminder covers every offset missed during downtime
+        reminders.sort();
         loop {
-            self.loop_body(&mut last_check).await?;
+            self.loop_body(&reminders).await?;
         }
     }
 
-    async fn loop_body(&mut self, last_check: &mut DateTime<Utc>) -> Result<(), AppError> {
+    async fn loop_body(&mut self, reminders: &[Duration]) -> Result<(), AppError> {
         info!("Checking soon to expire attempts");
         let now = Utc::now();
-        let after = *last_check + CONFIG.expiry_notifier.in_advance;
-        let until = now + CONFIG.expiry_notifier.in_advance;
+        for in_advance in reminders {
+            let due = self
+                .ar
+                .get_due_for_reminder(now, *in_advance)
+                .await
+                .map_internal("Failed to get active attempts")?;
 
-        let active = self
-            .ar
-            .get_active_between(after, until)
-            .await
-            .map_internal("Failed to get active attempts")?;
-
-        let users = active
-            .into_iter()
-            .map(|a| (UserId(a.who.to_u64()), a.state.until));
-        for (user, when) in users {
-            let dms = user.create_dm_channel(&self.ctx).await?;
-            dms.send_message(&self.ctx, |b| {
-                b.content(format!("Your attempt will soon expire: <t:{}:R>", when.timestamp()))
-            })
-            .await?;
+            for attempt in due {
+                let user = UserId(attempt.who.to_u64());
+                if let Err(e) = self.remind(user, attempt.state.until.timestamp()).await {
+                    warn!(error = %e, user = %user, "Failed to remind user");
+                }
+                // Failed reminders are not retried, closed DMs would fail on every check
+                self.ar
+                    .mark_reminded(&attempt.id, now)
+                    .await
+                    .map_internal("Failed to mark attempt as reminded")?;
+            }
         }
 
-        *last_check = now;
         tokio::time::sleep(CONFIG.expiry_notifier.check_interval).await;
         Ok(())
     }
+
+    async fn remind(&self, user: UserId, until: i64) -> serenity::Result<()> {
+        let dms = user.create_dm_channel(&self.ctx).await?;
+        dms.send_message(&self.ctx, |b| {
+            b.content(format!("Your attempt will soon expire: <t:{}:R>", until))
+        })
+        .await?;
+        Ok(())
+    }
 }
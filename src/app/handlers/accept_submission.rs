@@ -4,20 +4,23 @@ use crate::app::{
     error::ConvertError,
     permission::has_mod,
     rendering::{ModeRenderer, RoundRenderer},
-    util::{fetch_raw_image_from_attachment, raw_image_to_attachment},
+    trust::review_trust,
+    util::{
+        fetch_raw_image_from_attachment, forward_round, raw_image_to_attachment, reveal_partials,
+        spoiler,
+    },
 };
 use async_trait::async_trait;
 use gartic_on_demand::services::{
-    database::{
-        attempt::AttemptRepository, round::RoundRepository, user::UserRepository, ThingToU64,
-    },
+    database::{attempt::AttemptRepository, user::UserRepository, ThingToU64},
     gamemodes::GameLogic,
     provider::Provider,
     status_update::StatusUpdateWaker,
 };
-use poise::{Event, FrameworkContext};
+use poise::{serenity_prelude::ReactionType, Event, FrameworkContext};
 use serenity::prelude::Context;
 use std::cmp::Ordering;
+use tracing::error;
 
 #[derive(Debug)]
 pub struct AcceptSubmission;
@@ -33,7 +36,6 @@ impl AssetHandler for AcceptSubmission {
     ) -> Result<(), AppError> {
         let ar: AttemptRepository = data.get();
         let ur: UserRepository = data.get();
-        let rr: RoundRepository = data.get();
         match event {
             Event::ReactionAdd { add_reaction } => {
                 let user = add_reaction.user(&ctx).await?;
@@ -82,32 +84,33 @@ impl AssetHandler for AcceptSubmission {
                 if accepted {
                     let (channel, attachment, content) =
                         if round.round.round_no == round.round.mode.last_round() {
-                            let channel = match round.round.nsfw {
-                                true => CONFIG.channels.complete_nsfw,
-                                false => CONFIG.channels.complete,
-                            };
+                            let channel = CONFIG.ratings.get(round.round.rating).complete;
                             let attachment = round
                                 .round
                                 .mode
                                 .render_complete_image(&ctx, &round, &data.get(), old_attachment)
                                 .await?;
+                            let attachment =
+                                spoiler(attachment, CONFIG.ratings.get(round.round.rating).spoiler);
                             let content = round.render_complete_text();
                             (channel, attachment, content)
                         } else {
-                            let channel = match round.round.nsfw {
-                                true => CONFIG.channels.partial_nsfw,
-                                false => CONFIG.channels.partial,
-                            };
+                            let channel = CONFIG.ratings.get(round.round.rating).partial;
                             let attachment = round
                                 .round
                                 .mode
-                                .render_partial_image(old_attachment)
+                                .render_partial_image(round.round.round_no, old_attachment)
                                 .await?;
-                            let content = round.render_partial_text();
+                            let attachment = spoiler(attachment, CONFIG.spoilers.partial);
+                            let content = round.render_public_partial_text();
                             (channel, attachment, content)
                         };
                     let new_message = channel
-                        .send_message(ctx, |m| m.add_file(attachment).content(content))
+                        .send_message(ctx, |m| {
+                            m.add_file(attachment)
+                                .content(content)
+                                .reactions([ReactionType::Unicode(CONFIG.reactions.report.clone())])
+                        })
                         .await?;
                     let round = ar
                         .approve_pending_attempt(
@@ -118,9 +121,10 @@ impl AssetHandler for AcceptSubmission {
                         )
                         .await
                         .map_internal("Failed to accept/reject session")?;
-                    rr.forward_complete_round(&round.round, &round.attempt, round.round.forward())
-                        .await
-                        .map_internal("Failed to forward round")?;
+                    forward_round(ctx, &data.get(), &round).await?;
+                    if round.round.round_no == round.round.mode.last_round() {
+                        reveal_partials(&data.get(), &round).await?;
+                    }
                 } else {
                     let channel = CONFIG.channels.rejects;
                     let raw_image = fetch_raw_image_from_attachment(old_attachment)
@@ -137,6 +141,9 @@ impl AssetHandler for AcceptSubmission {
                 }
 
                 old_message.delete(&ctx).await?;
+                if let Err(e) = review_trust(ctx, &ar, &user).await {
+                    error!(error = %e, user = %user.id, "Failed to review trusted role");
+                }
 
                 let sw: StatusUpdateWaker = data.get();
                 sw.wake();
@@ -0,0 +1,86 @@
+use super::{AppData, AppError, AssetHandler};
+use crate::app::{config::CONFIG, error::ConvertError};
+use async_trait::async_trait;
+use gartic_on_demand::services::{
+    database::{
+        user::UserRepository,
+        vote::{Ballot, VoteRepository},
+        DbError,
+    },
+    provider::Provider,
+};
+use poise::{
+    serenity_prelude::{MessageId, ReactionType},
+    Event, FrameworkContext,
+};
+use serenity::prelude::Context;
+use std::cmp::Ordering;
+
+#[derive(Debug)]
+pub struct CastVote;
+
+#[async_trait]
+impl AssetHandler for CastVote {
+    async fn handle<'a>(
+        &self,
+        ctx: &Context,
+        event: &Event<'a>,
+        _fcx: FrameworkContext<'a, AppData, AppError>,
+        data: &AppData,
+    ) -> Result<(), AppError> {
+        let vr: VoteRepository = data.get();
+        let ur: UserRepository = data.get();
+        let (reaction, added) = match event {
+            Event::ReactionAdd { add_reaction } => (add_reaction, true),
+            Event::ReactionRemove { removed_reaction } => (removed_reaction, false),
+            _ => return Ok(()),
+        };
+        if !CONFIG
+            .ratings
+            .iter()
+            .any(|r| r.partial == reaction.channel_id)
+        {
+            return Ok(());
+        }
+        if reaction.emoji.unicode_partial_cmp(&CONFIG.reactions.vote) != Some(Ordering::Equal) {
+            return Ok(());
+        }
+        let user = reaction.user(&ctx).await?;
+        if user.bot {
+            return Ok(());
+        }
+        let voter = ur
+            .create_or_update_user(user.id.0, &user.name)
+            .await
+            .map_internal("Failed to update user")?;
+
+        if added {
+            match vr.cast(&voter, reaction.message_id.0).await {
+                // Only the latest vote counts, so its reaction is the only one left standing
+                Ok(Ballot {
+                    replaced: Some(replaced),
+                    ..
+                }) if replaced != reaction.message_id.0 => {
+                    reaction
+                        .channel_id
+                        .delete_reaction(
+                            &ctx,
+                            MessageId(replaced),
+                            Some(user.id),
+                            ReactionType::Unicode(CONFIG.reactions.vote.clone()),
+                        )
+                        .await?
+                }
+                Ok(_) => {}
+                // Reactions that do not count as votes would only mislead other voters
+                Err(DbError::NotFound) => reaction.delete(&ctx).await?,
+                Err(e) => Err(e).map_internal("Failed to cast vote")?,
+            }
+        } else {
+            vr.retract(&voter, reaction.message_id.0)
+                .await
+                .map_internal("Failed to retract vote")?;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
 pub mod accept_submission;
+pub mod cast_vote;
+pub mod rate_submission;
 pub mod remove_asset;
+pub mod report_submission;
+pub mod resolve_appeal;
+pub mod resolve_report;
 
 use super::{AppData, AppError};
 use async_trait::async_trait;
@@ -0,0 +1,62 @@
+use super::{AppData, AppError, AssetHandler};
+use crate::app::{
+    config::CONFIG, error::ConvertError, permission::has_mod, rendering::RoundRenderer,
+};
+use async_trait::async_trait;
+use gartic_on_demand::services::{
+    database::{attempt::AttemptRepository, round::RoundRepository},
+    provider::Provider,
+};
+use poise::{Event, FrameworkContext};
+use serenity::prelude::Context;
+
+/// Re-rates the chain of a submission under review when a moderator reacts with a rating's reaction.
+#[derive(Debug)]
+pub struct RateSubmission;
+
+#[async_trait]
+impl AssetHandler for RateSubmission {
+    async fn handle<'a>(
+        &self,
+        ctx: &Context,
+        event: &Event<'a>,
+        _fcx: FrameworkContext<'a, AppData, AppError>,
+        data: &AppData,
+    ) -> Result<(), AppError> {
+        let ar: AttemptRepository = data.get();
+        let rr: RoundRepository = data.get();
+        match event {
+            Event::ReactionAdd { add_reaction } => {
+                if add_reaction.channel_id != CONFIG.channels.moderation {
+                    return Ok(());
+                }
+                let Some(rating) = CONFIG.ratings.level_of_reaction(&add_reaction.emoji) else {
+                    return Ok(());
+                };
+                let user = add_reaction.user(&ctx).await?;
+                if user.bot {
+                    return Ok(());
+                }
+                has_mod(&ctx, &user).await?;
+
+                let mut round = ar
+                    .get_pending_attempt(add_reaction.message_id.0)
+                    .await
+                    .map_internal("Failed to get pending session")?;
+                round.inner.round = rr
+                    .set_rating(&round.round.id, rating)
+                    .await
+                    .map_internal("Failed to re-rate game")?;
+
+                let content = round.render_partial_text();
+                add_reaction
+                    .channel_id
+                    .edit_message(ctx, add_reaction.message_id, |m| m.content(content))
+                    .await?;
+                add_reaction.delete(ctx).await?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
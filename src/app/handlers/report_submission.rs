@@ -0,0 +1,118 @@
+use super::{AppData, AppError, AssetHandler};
+use crate::app::{
+    config::CONFIG,
+    error::ConvertError,
+    rendering::RoundRenderer,
+    util::{fetch_raw_image_from_attachment, raw_image_to_attachment},
+};
+use async_trait::async_trait;
+use gartic_on_demand::services::{
+    database::{
+        attempt::AttemptRepository, report::ReportRepository, user::UserRepository, DbError,
+    },
+    provider::Provider,
+};
+use poise::{
+    serenity_prelude::{Mentionable, ReactionType},
+    Event, FrameworkContext,
+};
+use serenity::prelude::Context;
+use std::cmp::Ordering;
+
+/// Queues published images for moderation when members react with the report reaction.
+#[derive(Debug)]
+pub struct ReportSubmission;
+
+#[async_trait]
+impl AssetHandler for ReportSubmission {
+    async fn handle<'a>(
+        &self,
+        ctx: &Context,
+        event: &Event<'a>,
+        _fcx: FrameworkContext<'a, AppData, AppError>,
+        data: &AppData,
+    ) -> Result<(), AppError> {
+        let ar: AttemptRepository = data.get();
+        let ur: UserRepository = data.get();
+        let rp: ReportRepository = data.get();
+        match event {
+            Event::ReactionAdd { add_reaction } => {
+                if !CONFIG
+                    .ratings
+                    .iter()
+                    .any(|r| [r.partial, r.complete].contains(&add_reaction.channel_id))
+                {
+                    return Ok(());
+                }
+                if add_reaction
+                    .emoji
+                    .unicode_partial_cmp(&CONFIG.reactions.report)
+                    != Some(Ordering::Equal)
+                {
+                    return Ok(());
+                }
+                let user = add_reaction.user(&ctx).await?;
+                if user.bot {
+                    return Ok(());
+                }
+                // Reports stay private, the button is only left for the next reporter
+                add_reaction.delete(ctx).await?;
+                let reporter = ur
+                    .create_or_update_user(user.id.0, &user.name)
+                    .await
+                    .map_internal("Failed to update user")?;
+
+                let round = match ar.get_approved_attempt(add_reaction.message_id.0).await {
+                    Ok(round) => round,
+                    Err(DbError::NotFound) => return Ok(()),
+                    Err(e) => Err(e).map_internal("Failed to get reported image")?,
+                };
+                let reported = rp
+                    .is_reported(&round.attempt.id)
+                    .await
+                    .map_internal("Failed to check reports")?;
+                if reported {
+                    return Ok(());
+                }
+
+                let message = add_reaction.message(&ctx).await?;
+                let attachment = message
+                    .attachments
+                    .first()
+                    .map_user("Reported message has no image")?;
+                let raw_image = fetch_raw_image_from_attachment(attachment)
+                    .await
+                    .map_internal("Failed to fetch image")?;
+                let content = format!(
+                    "Report by {} of {}\n{}",
+                    user.mention(),
+                    round.render_partial_text(),
+                    message.link()
+                );
+                let queued = CONFIG
+                    .channels
+                    .moderation
+                    .send_message(ctx, |m| {
+                        m.add_file(raw_image_to_attachment(raw_image.into()))
+                            .content(content)
+                            .reactions([
+                                ReactionType::Unicode(CONFIG.reactions.takedown.clone()),
+                                ReactionType::Unicode(CONFIG.reactions.dismiss.clone()),
+                            ])
+                    })
+                    .await?;
+                rp.create(
+                    queued.id.0,
+                    &reporter,
+                    &round.attempt.id,
+                    add_reaction.channel_id.0,
+                    add_reaction.message_id.0,
+                )
+                .await
+                .map_internal("Failed to create report")?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use super::{AppData, AppError, AssetHandler};
+use crate::app::{
+    config::CONFIG,
+    error::ConvertError,
+    permission::has_mod,
+    rendering::{ModeRenderer, RoundRenderer},
+    trust::review_trust,
+    util::{forward_round, reveal_partials, spoiler},
+};
+use async_trait::async_trait;
+use gartic_on_demand::services::{
+    database::{
+        appeal::AppealRepository, attempt::AttemptRepository, user::UserRepository, DbError,
+        ThingToU64,
+    },
+    gamemodes::GameLogic,
+    provider::Provider,
+    status_update::StatusUpdateWaker,
+};
+use poise::{serenity_prelude::ReactionType, Event, FrameworkContext};
+use serenity::prelude::Context;
+use std::cmp::Ordering;
+use tracing::{error, warn};
+
+/// Grants or denies appeals of rejected submissions, decided by a moderator other than the one who rejected them.
+/// Granted appeals publish the submission, forwarding its round or marking it conflicting if it was claimed since.
+#[derive(Debug)]
+pub struct ResolveAppeal;
+
+#[async_trait]
+impl AssetHandler for ResolveAppeal {
+    async fn handle<'a>(
+        &self,
+        ctx: &Context,
+        event: &Event<'a>,
+        _fcx: FrameworkContext<'a, AppData, AppError>,
+        data: &AppData,
+    ) -> Result<(), AppError> {
+        let ar: AttemptRepository = data.get();
+        let ur: UserRepository = data.get();
+        let ap: AppealRepository = data.get();
+        match event {
+            Event::ReactionAdd { add_reaction } => {
+                if add_reaction.channel_id != CONFIG.channels.appeals {
+                    return Ok(());
+                }
+                let grant = add_reaction
+                    .emoji
+                    .unicode_partial_cmp(&CONFIG.reactions.accept)
+                    == Some(Ordering::Equal);
+                let deny = add_reaction
+                    .emoji
+                    .unicode_partial_cmp(&CONFIG.reactions.reject)
+                    == Some(Ordering::Equal);
+                if !grant && !deny {
+                    return Ok(());
+                }
+                let user = add_reaction.user(&ctx).await?;
+                if user.bot {
+                    return Ok(());
+                }
+                has_mod(&ctx, &user).await?;
+                let moderator = ur
+                    .create_or_update_user(user.id.0, &user.name)
+                    .await
+                    .map_internal("Failed to update user")?;
+
+                let appeal = match ap.get_open(add_reaction.message_id.0).await {
+                    Ok(appeal) => appeal,
+                    Err(DbError::NotFound) => return Ok(()),
+                    Err(e) => Err(e).map_internal("Failed to get appeal")?,
+                };
+                if appeal.rejected_by == moderator.id {
+                    add_reaction.delete(ctx).await?;
+                    return Ok(());
+                }
+                let appellant = ur
+                    .get_user(appeal.appellant.to_u64())
+                    .await
+                    .map_internal("Failed to get user")?;
+
+                if grant {
+                    let round = ar
+                        .get_rejected_attempt(&appeal.attempt)
+                        .await
+                        .map_internal("Failed to get rejected session")?;
+                    let message = add_reaction.message(&ctx).await?;
+                    let old_attachment = &message.attachments[0];
+                    let (channel, attachment, content) =
+                        if round.round.round_no == round.round.mode.last_round() {
+                            let channel = CONFIG.ratings.get(round.round.rating).complete;
+                            let attachment = round
+                                .round
+                                .mode
+                                .render_complete_image(&ctx, &round, &data.get(), old_attachment)
+                                .await?;
+                            let attachment =
+                                spoiler(attachment, CONFIG.ratings.get(round.round.rating).spoiler);
+                            let content = round.render_complete_text();
+                            (channel, attachment, content)
+                        } else {
+                            let channel = CONFIG.ratings.get(round.round.rating).partial;
+                            let attachment = round
+                                .round
+                                .mode
+                                .render_partial_image(round.round.round_no, old_attachment)
+                                .await?;
+                            let attachment = spoiler(attachment, CONFIG.spoilers.partial);
+                            let content = round.render_public_partial_text();
+                            (channel, attachment, content)
+                        };
+                    let new_message = channel
+                        .send_message(ctx, |m| {
+                            m.add_file(attachment)
+                                .content(content)
+                                .reactions([ReactionType::Unicode(CONFIG.reactions.report.clone())])
+                        })
+                        .await?;
+                    let (round, conflicting) = ar
+                        .restore_rejected_attempt(&moderator, &appeal.attempt, new_message.id.0)
+                        .await
+                        .map_internal("Failed to restore session")?;
+                    if conflicting {
+                        warn!(round = %round.round.id, "Granted appeal conflicts with a later claim");
+                        CONFIG
+                            .channels
+                            .appeals
+                            .send_message(ctx, |m| {
+                                m.content(format!(
+                                    "{} was claimed again since its rejection and is now marked conflicting.\n{}",
+                                    round.render_partial_text(),
+                                    new_message.link()
+                                ))
+                            })
+                            .await?;
+                    } else {
+                        forward_round(ctx, &data.get(), &round).await?;
+                        if round.round.round_no == round.round.mode.last_round() {
+                            reveal_partials(&data.get(), &round).await?;
+                        }
+                    }
+                }
+                ap.resolve(&appeal, &moderator, grant)
+                    .await
+                    .map_internal("Failed to resolve appeal")?;
+                add_reaction
+                    .channel_id
+                    .delete_message(ctx, add_reaction.message_id)
+                    .await?;
+                if let Err(e) = review_trust(ctx, &ar, &appellant).await {
+                    error!(error = %e, user = %appellant.id, "Failed to review trusted role");
+                }
+
+                let sw: StatusUpdateWaker = data.get();
+                sw.wake();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use super::{AppData, AppError, AssetHandler};
+use crate::app::{
+    config::CONFIG, error::ConvertError, permission::has_mod, util::delete_message_if_exists,
+};
+use async_trait::async_trait;
+use gartic_on_demand::services::{
+    database::{
+        attempt::AttemptRepository, report::ReportRepository, user::UserRepository, DbError,
+    },
+    provider::Provider,
+    status_update::StatusUpdateWaker,
+};
+use poise::{
+    serenity_prelude::{ChannelId, MessageId},
+    Event, FrameworkContext,
+};
+use serenity::prelude::Context;
+use std::cmp::Ordering;
+use tracing::info;
+
+/// Upholds or dismisses reports in the moderation queue.
+/// Upheld reports take the image down, reopening its round or flagging its chain.
+#[derive(Debug)]
+pub struct ResolveReport;
+
+#[async_trait]
+impl AssetHandler for ResolveReport {
+    async fn handle<'a>(
+        &self,
+        ctx: &Context,
+        event: &Event<'a>,
+        _fcx: FrameworkContext<'a, AppData, AppError>,
+        data: &AppData,
+    ) -> Result<(), AppError> {
+        let ar: AttemptRepository = data.get();
+        let ur: UserRepository = data.get();
+        let rp: ReportRepository = data.get();
+        match event {
+            Event::ReactionAdd { add_reaction } => {
+                if add_reaction.channel_id != CONFIG.channels.moderation {
+                    return Ok(());
+                }
+                let takedown = add_reaction
+                    .emoji
+                    .unicode_partial_cmp(&CONFIG.reactions.takedown)
+                    == Some(Ordering::Equal);
+                let dismiss = add_reaction
+                    .emoji
+                    .unicode_partial_cmp(&CONFIG.reactions.dismiss)
+                    == Some(Ordering::Equal);
+                if !takedown && !dismiss {
+                    return Ok(());
+                }
+                let user = add_reaction.user(&ctx).await?;
+                if user.bot {
+                    return Ok(());
+                }
+                has_mod(&ctx, &user).await?;
+                let moderator = ur
+                    .create_or_update_user(user.id.0, &user.name)
+                    .await
+                    .map_internal("Failed to update user")?;
+
+                let report = match rp.get_open(add_reaction.message_id.0).await {
+                    Ok(report) => report,
+                    Err(DbError::NotFound) => return Ok(()),
+                    Err(e) => Err(e).map_internal("Failed to get report")?,
+                };
+
+                if takedown {
+                    // The image leaves Discord first, a retry after a failed takedown finds the report still open
+                    delete_message_if_exists(
+                        ctx,
+                        ChannelId(report.channel),
+                        MessageId(report.image),
+                    )
+                    .await?;
+                    let flagged = ar
+                        .take_down_approved_attempt(&moderator, report.image)
+                        .await
+                        .map_internal("Failed to take down image")?;
+                    info!(
+                        attempt = %report.attempt,
+                        flagged = flagged.len(),
+                        "Took down reported image"
+                    );
+                }
+                rp.resolve(&report, &moderator, takedown)
+                    .await
+                    .map_internal("Failed to resolve report")?;
+                add_reaction
+                    .channel_id
+                    .delete_message(ctx, add_reaction.message_id)
+                    .await?;
+
+                let sw: StatusUpdateWaker = data.get();
+                sw.wake();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use self::{config::CONFIG, error::AppError};
 use gartic_on_demand::services::{
     database::{migrations::Migrator, Database},
+    gamemodes::ross::Ross,
     provider::Provider,
     status_update::StatusUpdateWaker,
 };
@@ -13,11 +14,16 @@ pub mod error;
 pub mod expiry_notifier;
 pub mod handlers;
 pub mod log;
+pub mod party_runner;
 pub mod permission;
+pub mod reliability;
 pub mod rendering;
 pub mod response;
+pub mod spoiler_revealer;
 pub mod stats_printer;
+pub mod trust;
 pub mod util;
+pub mod vote_closer;
 
 #[derive(Clone)]
 pub struct AppData {
@@ -27,6 +33,9 @@ pub struct AppData {
 
 impl AppData {
     pub async fn setup(sw: StatusUpdateWaker) -> Result<Self, Box<dyn Error>> {
+        if let Some(attributes) = CONFIG.modes.ross.attributes {
+            Ross::set_attributes(attributes.0);
+        }
         let db = Database::setup(&CONFIG.database).await?;
         Migrator::new(&CONFIG.database.migrator)
             .migrate(&db)
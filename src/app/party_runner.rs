@@ -0,0 +1,148 @@
+use crate::app::{
+    config::CONFIG,
+    error::ConvertError,
+    rendering::render_skipped,
+    util::{dm_prompt, image_to_attachment, next_round},
+};
+
+use super::error::AppError;
+use chrono::Utc;
+use gartic_on_demand::services::{
+    database::{
+        party::{Party, PartyRepository, Turn},
+        Database, Record,
+    },
+    gamemodes::GameLogic,
+    provider::Provider,
+};
+use serde::Deserialize;
+use serde_with::{serde_as, DurationSeconds};
+use serenity::prelude::Context;
+use tracing::{error, info};
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct PartyRunnerConfig {
+    #[serde_as(as = "DurationSeconds<u64>")]
+    check_interval: std::time::Duration,
+}
+
+pub struct PartyRunner {
+    pr: PartyRepository,
+    db: Database,
+    ctx: Context,
+}
+
+impl PartyRunner {
+    pub fn new(db: Database, ctx: Context) -> Self {
+        Self {
+            pr: db.get(),
+            db: db.clone(),
+            ctx,
+        }
+    }
+
+    pub async fn run(mut self) {
+        loop {
+            if let Err(e) = self.run_internal().await {
+                error!(error = %e, "Party runner error");
+            }
+        }
+    }
+
+    pub async fn run_internal(&mut self) -> Result<(), AppError> {
+        info!("Starting party runner");
+        loop {
+            self.loop_body().await?;
+        }
+    }
+
+    async fn loop_body(&mut self) -> Result<(), AppError> {
+        info!("Advancing parties past their deadline");
+        let parties = self
+            .pr
+            .get_due(Utc::now())
+            .await
+            .map_internal("Failed to get parties")?;
+
+        for party in parties {
+            if let Err(e) = self.advance(&party).await {
+                error!(error = %e, party = %party.id, "Failed to advance party");
+            }
+        }
+
+        tokio::time::sleep(CONFIG.party_runner.check_interval).await;
+        Ok(())
+    }
+
+    /// Collects every chain's turn, waiting for turns in review, then rotates chains between players.
+    async fn advance(&self, party: &Record<Party>) -> Result<(), AppError> {
+        let mut rounds = Vec::with_capacity(party.chains.len());
+        let mut attempts = Vec::with_capacity(party.chains.len());
+        for (chain, round) in party.chains.iter().enumerate() {
+            let player = &party.players[party.player_of(chain)];
+            let turn = self
+                .pr
+                .get_turn(player, round)
+                .await
+                .map_internal("Failed to get party turn")?;
+            match turn {
+                Turn::Played(turn) => {
+                    rounds.push(turn.inner.round);
+                    attempts.push(Some(turn.attempt.id));
+                }
+                Turn::InReview => {
+                    info!(party = %party.id, "Waiting for party turns in review");
+                    return Ok(());
+                }
+                Turn::Missed(round) => {
+                    rounds.push(round);
+                    attempts.push(None);
+                }
+            }
+        }
+
+        let next = match party.is_last_round() {
+            true => vec![],
+            false => rounds.iter().map(|r| next_round(r)).collect(),
+        };
+        let advanced = self
+            .pr
+            .advance(party, &attempts, &next)
+            .await
+            .map_internal("Failed to advance party")?;
+
+        for _ in attempts.iter().filter(|a| a.is_none()) {
+            if let Err(e) = self.announce_skip(party).await {
+                error!(error = %e, party = %party.id, "Failed to announce skipped turn");
+            }
+        }
+        for round in &advanced.rounds {
+            if let Err(e) = dm_prompt(&self.ctx, &self.db, round).await {
+                error!(error = %e, attempt = %round.attempt.id, "Failed to send party prompt");
+            }
+        }
+        Ok(())
+    }
+
+    async fn announce_skip(&self, party: &Party) -> Result<(), AppError> {
+        // Party chains are always started at the mildest rating
+        let rating = CONFIG.ratings.get(0);
+        let channel = match party.is_last_round() {
+            true => rating.complete,
+            false => rating.partial,
+        };
+        let image = render_skipped(party.mode.canvas(party.round_no));
+        let content = format!(
+            "{:?} mode round {} was skipped",
+            party.mode,
+            party.round_no + 1
+        );
+        channel
+            .send_message(&self.ctx, |m| {
+                m.add_file(image_to_attachment(image)).content(content)
+            })
+            .await?;
+        Ok(())
+    }
+}
@@ -6,11 +6,18 @@ use super::{
     error::{AppError, OptionEmptyError},
 };
 
-pub async fn is_adult(cache_http: impl CacheHttp, user: &User) -> serenity::Result<bool> {
-    let is_trusted: bool = user
-        .has_role(&cache_http, CONFIG.guild, CONFIG.roles.adult)
-        .await?;
-    Ok(is_trusted)
+/// Whether the user holds the roles of every rating up to `level`.
+pub async fn can_play_rating(
+    cache_http: impl CacheHttp,
+    user: &User,
+    level: u64,
+) -> serenity::Result<bool> {
+    for role in CONFIG.ratings.up_to(level).filter_map(|r| r.role) {
+        if !user.has_role(&cache_http, CONFIG.guild, role).await? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
 }
 
 pub async fn is_trusted(cache_http: impl CacheHttp, user: &User) -> serenity::Result<bool> {
@@ -30,7 +37,7 @@ async fn is_mod(cache_http: impl CacheHttp, user: &User) -> serenity::Result<boo
     Ok(is_mod)
 }
 
-async fn is_admin(cache_http: impl CacheHttp, user: &User) -> serenity::Result<bool> {
+pub async fn is_admin(cache_http: impl CacheHttp, user: &User) -> serenity::Result<bool> {
     user.has_role(&cache_http, CONFIG.guild, CONFIG.roles.admin)
         .await
 }
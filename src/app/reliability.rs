@@ -0,0 +1,50 @@
+use super::{config::CONFIG, error::ConvertError, AppError};
+use chrono::Duration;
+use gartic_on_demand::services::{
+    database::{
+        attempt::{AttemptRepository, ReliabilityRecord},
+        user::User,
+        Record,
+    },
+    gamemodes::{GameLogic, Mode},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ReliabilityConfig {
+    /// Number of most recent approved, cancelled or expired attempts the score looks at.
+    pub window: u32,
+    /// Attempts needed before the score has any effect.
+    pub min_attempts: u64,
+    pub min_score: f64,
+    /// Time limits of users scoring below `min_score` are multiplied by this.
+    pub time_limit_factor: f64,
+}
+
+impl ReliabilityConfig {
+    pub fn is_unreliable(&self, record: &ReliabilityRecord) -> bool {
+        record.total() >= self.min_attempts && record.score() < self.min_score
+    }
+}
+
+/// Time limit of a round for the user, shortened for users who often cancel or let attempts expire.
+pub async fn time_limit_for(
+    ar: &AttemptRepository,
+    user: &Record<User>,
+    mode: Mode,
+    round_no: u64,
+) -> Result<Duration, AppError> {
+    let time_limit = mode.time_limit(round_no);
+    let Some(config) = &CONFIG.reliability else {
+        return Ok(time_limit);
+    };
+    let record = ar
+        .get_reliability_record(user, config.window)
+        .await
+        .map_internal("Failed to get reliability record")?;
+    if !config.is_unreliable(&record) {
+        return Ok(time_limit);
+    }
+    let millis = time_limit.num_milliseconds() as f64 * config.time_limit_factor;
+    Ok(Duration::milliseconds(millis as i64))
+}
@@ -1,31 +1,52 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap};
 
+use ab_glyph::FontRef;
 use async_trait::async_trait;
 use gartic_on_demand::services::{
-    database::{assets::ImageRepository, attempt::Active, round::RoundWithAttempts, ThingToU64},
-    gamemodes::{evolution::Evolution, ross::Ross, Mode},
-    image_processing::{concat_vertical, normalize_image_aoi, RgbaConvert},
+    database::{
+        attempt::{Active, Approved, Attempt},
+        round::RoundWithAttempts,
+        user::UserRepository,
+        Database, Record, ThingToU64,
+    },
+    gamemodes::{evolution::Evolution, ross::Ross, Canvas, GameLogic, Mode},
+    image_processing::{
+        concat_vertical, normalize_image_aoi, render_label, Cell, Grid, RgbaConvert,
+    },
+    provider::Provider,
 };
+use image::{GenericImage, Rgba, RgbaImage};
+use lazy_static::lazy_static;
 use poise::serenity_prelude::{Attachment, AttachmentType};
 use serenity::http::Http;
+use tracing::warn;
 
 use super::{
     config::CONFIG,
     error::{AppError, ConvertError},
-    util::{extract_2x2_image, extract_nx1_image, fetch_image_from_attachment},
+    util::{extract_grid_image, extract_nx1_image, fetch_image_from_attachment},
 };
 
+lazy_static! {
+    static ref FONT: FontRef<'static> =
+        FontRef::try_from_slice(include_bytes!("../../assets/fonts/DejaVuSans.ttf"))
+            .expect("Failed to parse caption font");
+}
+
+const CAPTION_HEIGHT: u32 = 32;
+
 #[async_trait]
 pub trait ModeRenderer {
     async fn render_prompt_image(
         &self,
         ctx: &(impl AsRef<Http> + Send + Sync),
         round: &RoundWithAttempts<Active>,
-        ir: &ImageRepository,
+        db: &Database,
     ) -> Result<AttachmentType<'static>, AppError>;
 
     async fn render_partial_image(
         &self,
+        round_no: u64,
         attachment: &Attachment,
     ) -> Result<AttachmentType<'static>, AppError>;
 
@@ -33,7 +54,7 @@ pub trait ModeRenderer {
         &self,
         ctx: &(impl AsRef<Http> + Send + Sync),
         round: &RoundWithAttempts<T>,
-        ir: &ImageRepository,
+        db: &Database,
         attachment: &Attachment,
     ) -> Result<AttachmentType<'static>, AppError>;
 
@@ -48,21 +69,22 @@ impl ModeRenderer for Mode {
         &self,
         ctx: &(impl AsRef<Http> + Send + Sync),
         round: &RoundWithAttempts<Active>,
-        ir: &ImageRepository,
+        db: &Database,
     ) -> Result<AttachmentType<'static>, AppError> {
         match &self {
-            Mode::Ross => Ross.render_prompt_image(ctx, round, ir).await,
-            Mode::Evolution => Evolution.render_prompt_image(ctx, round, ir).await,
+            Mode::Ross => Ross.render_prompt_image(ctx, round, db).await,
+            Mode::Evolution => Evolution.render_prompt_image(ctx, round, db).await,
         }
     }
 
     async fn render_partial_image(
         &self,
+        round_no: u64,
         attachment: &Attachment,
     ) -> Result<AttachmentType<'static>, AppError> {
         match &self {
-            Mode::Ross => Ross.render_partial_image(attachment).await,
-            Mode::Evolution => Evolution.render_partial_image(attachment).await,
+            Mode::Ross => Ross.render_partial_image(round_no, attachment).await,
+            Mode::Evolution => Evolution.render_partial_image(round_no, attachment).await,
         }
     }
 
@@ -70,14 +92,14 @@ impl ModeRenderer for Mode {
         &self,
         ctx: &(impl AsRef<Http> + Send + Sync),
         round: &RoundWithAttempts<T>,
-        ir: &ImageRepository,
+        db: &Database,
         attachment: &Attachment,
     ) -> Result<AttachmentType<'static>, AppError> {
         match &self {
-            Mode::Ross => Ross.render_complete_image(ctx, round, ir, attachment).await,
+            Mode::Ross => Ross.render_complete_image(ctx, round, db, attachment).await,
             Mode::Evolution => {
                 Evolution
-                    .render_complete_image(ctx, round, ir, attachment)
+                    .render_complete_image(ctx, round, db, attachment)
                     .await
             }
         }
@@ -111,9 +133,14 @@ impl ModeRenderer for Ross {
         &self,
         ctx: &(impl AsRef<Http> + Send + Sync),
         round: &RoundWithAttempts<Active>,
-        ir: &ImageRepository,
+        db: &Database,
     ) -> Result<AttachmentType<'static>, AppError> {
-        let image = extract_2x2_image(ctx, ir, &round.previous, round.round.nsfw).await?;
+        let canvas = self.canvas(0);
+        let n = self.last_round() as usize;
+        let reveal = !CONFIG.modes.get(Mode::Ross).anonymous;
+        let captions =
+            render_captions(ctx, db, Mode::Ross, &round.previous, canvas.width, reveal).await;
+        let image = extract_grid_image(ctx, &db.get(), round, n, canvas, &captions).await?;
         let attachment = AttachmentType::Bytes {
             data: std::borrow::Cow::Owned(image.to_png()),
             filename: "prompt.png".to_owned(),
@@ -123,12 +150,14 @@ impl ModeRenderer for Ross {
 
     async fn render_partial_image(
         &self,
+        round_no: u64,
         attachment: &Attachment,
     ) -> Result<AttachmentType<'static>, AppError> {
         let image = fetch_image_from_attachment(attachment)
             .await
             .map_user("Attachment is not a valid image")?;
-        let image = normalize_image_aoi(&image, CONFIG.image.width, CONFIG.image.height);
+        let canvas = self.canvas(round_no);
+        let image = normalize_image_aoi(&image, canvas.width, canvas.height);
         let attachment = AttachmentType::Bytes {
             data: Cow::Owned(image.to_png().to_vec()),
             filename: "partial.png".to_owned(),
@@ -140,14 +169,21 @@ impl ModeRenderer for Ross {
         &self,
         ctx: &(impl AsRef<Http> + Send + Sync),
         round: &RoundWithAttempts<T>,
-        ir: &ImageRepository,
+        db: &Database,
         attachment: &Attachment,
     ) -> Result<AttachmentType<'static>, AppError> {
         let image = fetch_image_from_attachment(attachment)
             .await
             .map_user("Attachment is not a valid image")?;
-        let attributes = extract_2x2_image(&ctx, ir, &round.previous, round.round.nsfw).await?;
-        let image = normalize_image_aoi(&image, 2 * CONFIG.image.width, 2 * CONFIG.image.height);
+        let canvas = self.canvas(0);
+        let n = self.last_round() as usize;
+        let captions =
+            render_captions(ctx, db, Mode::Ross, &round.previous, canvas.width, true).await;
+        let attributes = extract_grid_image(&ctx, &db.get(), round, n, canvas, &captions).await?;
+        let canvas = self.canvas(self.last_round());
+        let image = normalize_image_aoi(&image, canvas.width, canvas.height);
+        let author = round.attempt.who.to_u64();
+        let image = caption_final(ctx, db, Mode::Ross, self.last_round(), author, image).await;
         let image = concat_vertical(&[attributes, image]);
         let attachment = AttachmentType::Bytes {
             data: Cow::Owned(image.to_png().to_vec()),
@@ -180,9 +216,20 @@ impl ModeRenderer for Evolution {
         &self,
         ctx: &(impl AsRef<Http> + Send + Sync),
         round: &RoundWithAttempts<Active>,
-        ir: &ImageRepository,
+        db: &Database,
     ) -> Result<AttachmentType<'static>, AppError> {
-        let image = extract_nx1_image(ctx, ir, &round.previous, round.round.nsfw, 3).await?;
+        let canvas = self.canvas(round.round.round_no);
+        let reveal = !CONFIG.modes.get(Mode::Evolution).anonymous;
+        let captions = render_captions(
+            ctx,
+            db,
+            Mode::Evolution,
+            &round.previous,
+            canvas.width,
+            reveal,
+        )
+        .await;
+        let image = extract_nx1_image(ctx, &db.get(), round, 3, canvas, &captions).await?;
         let attachment = AttachmentType::Bytes {
             data: std::borrow::Cow::Owned(image.to_png()),
             filename: "prompt.png".to_owned(),
@@ -192,12 +239,14 @@ impl ModeRenderer for Evolution {
 
     async fn render_partial_image(
         &self,
+        round_no: u64,
         attachment: &Attachment,
     ) -> Result<AttachmentType<'static>, AppError> {
         let image = fetch_image_from_attachment(attachment)
             .await
             .map_user("Attachment is not a valid image")?;
-        let image = normalize_image_aoi(&image, CONFIG.image.width, CONFIG.image.height);
+        let canvas = self.canvas(round_no);
+        let image = normalize_image_aoi(&image, canvas.width, canvas.height);
         let attachment = AttachmentType::Bytes {
             data: Cow::Owned(image.to_png().to_vec()),
             filename: "partial.png".to_owned(),
@@ -209,14 +258,34 @@ impl ModeRenderer for Evolution {
         &self,
         ctx: &(impl AsRef<Http> + Send + Sync),
         round: &RoundWithAttempts<T>,
-        ir: &ImageRepository,
+        db: &Database,
         attachment: &Attachment,
     ) -> Result<AttachmentType<'static>, AppError> {
         let image = fetch_image_from_attachment(attachment)
             .await
             .map_user("Attachment is not a valid image")?;
-        let previous = extract_nx1_image(ctx, ir, &round.previous, round.round.nsfw, 2).await?;
-        let image = normalize_image_aoi(&image, CONFIG.image.width, CONFIG.image.height);
+        let canvas = self.canvas(round.round.round_no);
+        let captions = render_captions(
+            ctx,
+            db,
+            Mode::Evolution,
+            &round.previous,
+            canvas.width,
+            true,
+        )
+        .await;
+        let previous = extract_nx1_image(ctx, &db.get(), round, 2, canvas, &captions).await?;
+        let image = normalize_image_aoi(&image, canvas.width, canvas.height);
+        let author = round.attempt.who.to_u64();
+        let image = caption_final(
+            ctx,
+            db,
+            Mode::Evolution,
+            round.round.round_no,
+            author,
+            image,
+        )
+        .await;
         let image = concat_vertical(&[previous, image]);
         let attachment = AttachmentType::Bytes {
             data: Cow::Owned(image.to_png().to_vec()),
@@ -243,17 +312,106 @@ impl ModeRenderer for Evolution {
     }
 }
 
+fn render_caption(round_no: u64, author: Option<&str>, width: u32) -> RgbaImage {
+    let text = match author {
+        Some(author) => format!("Round {} - {}", round_no + 1, author),
+        None => format!("Round {}", round_no + 1),
+    };
+    render_label(&*FONT, &text, width, CAPTION_HEIGHT)
+}
+
+/// Display names of the authors in the guild, falling back to the names stored with their users.
+async fn author_names(
+    ctx: &(impl AsRef<Http> + Send + Sync),
+    db: &Database,
+    authors: &[u64],
+) -> HashMap<u64, String> {
+    let ur: UserRepository = db.get();
+    let mut names = HashMap::new();
+    for &author in authors {
+        if names.contains_key(&author) {
+            continue;
+        }
+        let name = match CONFIG.guild.member(ctx.as_ref(), author).await {
+            Ok(member) => Some(member.display_name().into_owned()),
+            Err(e) => {
+                warn!(error = ?e, author, "Failed to fetch caption author");
+                ur.get_user(author).await.ok().map(|user| user.name.clone())
+            }
+        };
+        if let Some(name) = name {
+            names.insert(author, name);
+        }
+    }
+    names
+}
+
+/// Captions of previous rounds, authors are left out of prompts in anonymous modes.
+async fn render_captions(
+    ctx: &(impl AsRef<Http> + Send + Sync),
+    db: &Database,
+    mode: Mode,
+    previous: &[Record<Attempt<Approved>>],
+    width: u32,
+    reveal: bool,
+) -> Vec<RgbaImage> {
+    if !CONFIG.modes.get(mode).captions {
+        return Vec::new();
+    }
+    let names = if reveal {
+        let authors = previous.iter().map(|a| a.who.to_u64()).collect::<Vec<_>>();
+        author_names(ctx, db, &authors).await
+    } else {
+        HashMap::new()
+    };
+    previous
+        .iter()
+        .enumerate()
+        .map(|(round_no, attempt)| {
+            let author = names.get(&attempt.who.to_u64()).map(String::as_str);
+            render_caption(round_no as u64, author, width)
+        })
+        .collect()
+}
+
+async fn caption_final(
+    ctx: &(impl AsRef<Http> + Send + Sync),
+    db: &Database,
+    mode: Mode,
+    round_no: u64,
+    author: u64,
+    image: RgbaImage,
+) -> RgbaImage {
+    if !CONFIG.modes.get(mode).captions {
+        return image;
+    }
+    let names = author_names(ctx, db, &[author]).await;
+    let author = names.get(&author).map(String::as_str);
+    let caption = render_caption(round_no, author, image.width());
+    Grid::new(1, 1).compose(&[Cell::labelled(&image, &caption)])
+}
+
+/// Placeholder image standing in for a missed turn.
+pub fn render_skipped(canvas: Canvas) -> RgbaImage {
+    let label = render_label(&*FONT, "Skipped", canvas.width, CAPTION_HEIGHT);
+    let mut image = RgbaImage::from_pixel(canvas.width, canvas.height, Rgba([255, 255, 255, 255]));
+    let y = canvas.height.saturating_sub(CAPTION_HEIGHT) / 2;
+    image.copy_from(&label, 0, y).unwrap();
+    image
+}
+
 pub trait RoundRenderer {
     fn render_partial_text(&self) -> String;
+    fn render_public_partial_text(&self) -> String;
     fn render_complete_text(&self) -> String;
 }
 
 impl<T> RoundRenderer for RoundWithAttempts<T> {
     fn render_partial_text(&self) -> String {
-        let sfw: &str = if self.round.nsfw { "NSFW " } else { "" };
+        let rating = CONFIG.ratings.prefix(self.round.rating);
         let content = format!(
             "{}{:?} mode round {} by {}",
-            sfw,
+            rating,
             self.round.mode,
             self.round.round_no + 1,
             self.round
@@ -263,8 +421,22 @@ impl<T> RoundRenderer for RoundWithAttempts<T> {
         content
     }
 
+    /// Partial text for public channels, anonymous modes keep authors hidden until the game completes.
+    fn render_public_partial_text(&self) -> String {
+        if !CONFIG.modes.get(self.round.mode).anonymous {
+            return self.render_partial_text();
+        }
+        let rating = CONFIG.ratings.prefix(self.round.rating);
+        format!(
+            "{}{:?} mode round {}",
+            rating,
+            self.round.mode,
+            self.round.round_no + 1
+        )
+    }
+
     fn render_complete_text(&self) -> String {
-        let sfw: &str = if self.round.nsfw { "NSFW " } else { "" };
+        let rating = CONFIG.ratings.prefix(self.round.rating);
         let others = self
             .previous
             .iter()
@@ -272,7 +444,7 @@ impl<T> RoundRenderer for RoundWithAttempts<T> {
             .collect::<Vec<_>>();
         let content = format!(
             "{}{:?} mode round {} by {}",
-            sfw,
+            rating,
             self.round.mode,
             self.round.round_no + 1,
             self.round
@@ -0,0 +1,87 @@
+use crate::app::{config::CONFIG, error::ConvertError, util::reveal_partial};
+
+use super::error::AppError;
+use chrono::Utc;
+use gartic_on_demand::services::{
+    database::{
+        round::{Round, RoundRepository},
+        Database, Record,
+    },
+    provider::Provider,
+};
+use poise::serenity_prelude::MessageId;
+use serde::Deserialize;
+use serde_with::{serde_as, DurationSeconds};
+use serenity::prelude::Context;
+use tracing::{error, info};
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct SpoilerRevealerConfig {
+    #[serde_as(as = "DurationSeconds<u64>")]
+    check_interval: std::time::Duration,
+}
+
+/// Reveals spoilered partial images of complete games once their reveal time passes.
+pub struct SpoilerRevealer {
+    rr: RoundRepository,
+    ctx: Context,
+}
+
+impl SpoilerRevealer {
+    pub fn new(db: Database, ctx: Context) -> Self {
+        Self { rr: db.get(), ctx }
+    }
+
+    pub async fn run(mut self) {
+        loop {
+            if let Err(e) = self.run_internal().await {
+                error!(error = %e, "Spoiler revealer error");
+            }
+        }
+    }
+
+    pub async fn run_internal(&mut self) -> Result<(), AppError> {
+        info!("Starting spoiler revealer");
+        loop {
+            self.loop_body().await?;
+        }
+    }
+
+    async fn loop_body(&mut self) -> Result<(), AppError> {
+        info!("Revealing partial images of complete games");
+        let rounds = self
+            .rr
+            .get_due_reveals(Utc::now())
+            .await
+            .map_internal("Failed to get due reveals")?;
+
+        for round in rounds {
+            if let Err(e) = self.reveal(&round).await {
+                error!(error = %e, round = %round.id, "Failed to reveal partial images");
+            }
+        }
+
+        tokio::time::sleep(CONFIG.spoiler_revealer.check_interval).await;
+        Ok(())
+    }
+
+    async fn reveal(&self, round: &Record<Round>) -> Result<(), AppError> {
+        let images = self
+            .rr
+            .get_partial_images(&round.id)
+            .await
+            .map_internal("Failed to get partial images")?;
+        let channels = CONFIG.ratings.partial_channels(round.rating);
+        for image in images {
+            let message = MessageId(image);
+            // Images already revealed are left alone, so a failed reveal is retried as a whole
+            reveal_partial(&self.ctx.http, &channels, message).await?;
+        }
+        self.rr
+            .finish_reveal(&round.id)
+            .await
+            .map_internal("Failed to finish reveal")?;
+        Ok(())
+    }
+}
@@ -119,7 +119,7 @@ impl StatsPrinter {
     async fn unallocated_rounds(&mut self) -> Result<String, AppError> {
         let mut unallocated = self
             .sr
-            .get_unallocated_rounds()
+            .get_unallocated_rounds(None, None)
             .await
             .map_internal("Failed to fetch incomplete games")?;
         unallocated.retain(|u| u.round_no > 0 && u.round_no <= u.mode.last_round());
@@ -134,10 +134,15 @@ impl StatsPrinter {
         Ok(unallocated)
     }
 
-    fn unallocated_round_to_string(round: &UnallocatedRound) -> String {
+    pub fn unallocated_round_to_string(round: &UnallocatedRound) -> String {
         format!(
-            "- {}{:?} mode round {} - available {}",
-            if round.nsfw { "NSFW " } else { "" },
+            "- {}{}{:?} mode round {} - available {}",
+            CONFIG.ratings.prefix(round.rating),
+            round
+                .theme
+                .as_ref()
+                .map(|theme| format!("{} ", theme))
+                .unwrap_or_default(),
             round.mode,
             round.round_no + 1,
             round.unallocated
@@ -166,7 +171,7 @@ impl StatsPrinter {
         format!(
             "- <@{}> - {}{:?} mode round {}",
             user.user.id(),
-            if user.round.nsfw { "NSFW " } else { "" },
+            CONFIG.ratings.prefix(user.round.rating),
             user.round.mode,
             user.round.round_no + 1
         )
@@ -0,0 +1,85 @@
+use super::{config::CONFIG, error::ConvertError, AppError};
+use gartic_on_demand::services::database::{
+    attempt::{AttemptRepository, ReviewRecord},
+    user::User,
+    Record, ThingToU64,
+};
+use serde::Deserialize;
+use serenity::{model::prelude::UserId, prelude::Context};
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct TrustConfig {
+    /// Number of most recent submissions the rules look at.
+    pub window: u32,
+    /// Approved submissions needed for promotion, and reviewed submissions needed for any change.
+    pub min_approved: u64,
+    pub max_rejection_rate: f64,
+    /// Users who are never promoted automatically, they can still be demoted.
+    pub never_promote: Vec<UserId>,
+}
+
+impl TrustConfig {
+    /// Whether the record earns the trusted role, loses it, or leaves it as is.
+    fn verdict(&self, record: &ReviewRecord) -> Option<bool> {
+        if record.approved + record.rejected < self.min_approved {
+            return None;
+        }
+        let rate = record.rejection_rate();
+        if rate >= self.max_rejection_rate {
+            Some(false)
+        } else if record.approved >= self.min_approved {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// Grants or revokes the trusted role of a user after one of their submissions was reviewed.
+pub async fn review_trust(
+    ctx: &Context,
+    ar: &AttemptRepository,
+    user: &Record<User>,
+) -> Result<(), AppError> {
+    let Some(config) = &CONFIG.trust else {
+        return Ok(());
+    };
+    let record = ar
+        .get_review_record(user, config.window)
+        .await
+        .map_internal("Failed to get review record")?;
+    let Some(trusted) = config.verdict(&record) else {
+        return Ok(());
+    };
+    let user_id = UserId(user.id.to_u64());
+    if trusted && config.never_promote.contains(&user_id) {
+        return Ok(());
+    }
+
+    let mut member = CONFIG.guild.member(ctx, user_id).await?;
+    let role = CONFIG.roles.trusted;
+    let verb = match (trusted, member.roles.contains(&role)) {
+        (true, false) => {
+            member.add_role(ctx, role).await?;
+            "promoted to"
+        }
+        (false, true) => {
+            member.remove_role(ctx, role).await?;
+            "demoted from"
+        }
+        _ => return Ok(()),
+    };
+    info!(user = %user.id, trusted, "Changed trusted role");
+    CONFIG
+        .channels
+        .moderation
+        .send_message(ctx, |m| {
+            m.content(format!(
+                "<@{}> was {} trusted: {} approved and {} rejected of their last {} submissions",
+                user_id.0, verb, record.approved, record.rejected, config.window
+            ))
+        })
+        .await?;
+    Ok(())
+}
@@ -1,27 +1,40 @@
 use super::{
     config::CONFIG,
     error::{AppError, ConvertError},
-    rendering::ModeRenderer,
+    reliability::time_limit_for,
+    rendering::{render_skipped, ModeRenderer},
     response::ResponseContext,
     AppContext,
 };
 use bytes::Bytes;
+use chrono::Utc;
 use gartic_on_demand::services::{
     database::{
         assets::{AssetKind, ImageRepository},
-        attempt::{Active, Approved, Attempt},
-        round::RoundWithAttempts,
-        Record,
+        attempt::{Active, Approved, Attempt, AttemptRepository},
+        round::{Round, RoundRepository, RoundWithAttempts, RoundWithPreviousAttempts},
+        subscription::{Subscription, SubscriptionRepository},
+        user::{User, UserRepository},
+        Database, DbError, Record, ThingToU64,
     },
-    gamemodes::GameLogic,
-    image_processing::{concat_2_2, concat_vertical, RgbaConvert},
+    gamemodes::{Canvas, GameLogic},
+    image_processing::{normalize_image, Cell, Grid, RgbaConvert},
     provider::Provider,
 };
 use image::RgbaImage;
 use mime::IMAGE_PNG;
-use poise::serenity_prelude::{Attachment, AttachmentType, ChannelId, MessageId};
-use reqwest::header::{self, HeaderValue};
-use serenity::http::Http;
+use poise::serenity_prelude::{
+    Attachment, AttachmentType, ChannelId, Message, MessageId, ReactionType, UserId,
+};
+use poise::AutocompleteChoice;
+use reqwest::{
+    header::{self, HeaderValue},
+    StatusCode,
+};
+use serenity::{http::Http, prelude::Context};
+use std::{borrow::Cow, sync::Arc};
+use surrealdb::sql::Thing;
+use tracing::{error, warn};
 
 #[derive(Debug, thiserror::Error)]
 pub enum FetchError {
@@ -56,48 +69,83 @@ pub async fn fetch_image_from_attachment(attachment: &Attachment) -> FetchResult
     Ok(image)
 }
 
-pub async fn extract_2x2_image(
+pub async fn extract_grid_image(
     ctx: &(impl AsRef<Http> + Send + Sync),
     ir: &ImageRepository,
-    attempts: &[Record<Attempt<Approved>>],
-    nsfw: bool,
+    round: &RoundWithPreviousAttempts,
+    n: usize,
+    canvas: Canvas,
+    labels: &[RgbaImage],
 ) -> Result<RgbaImage, AppError> {
-    let n = 4;
-    let mut images = Vec::with_capacity(n);
-    complement_submissions(ctx, &mut images, attempts, nsfw).await?;
-    complement_draw_this(ctx, &mut images, ir, n).await?;
-    complement_in_construction(ctx, &mut images, ir, n).await?;
-    let image = concat_2_2(&images);
+    let images = extract_images(ctx, ir, round, n, canvas).await?;
+    let image = Grid::fitting(n as u32).compose(&cells(&images, labels));
     Ok(image)
 }
 
 pub async fn extract_nx1_image(
     ctx: &(impl AsRef<Http> + Send + Sync),
     ir: &ImageRepository,
-    attempts: &[Record<Attempt<Approved>>],
-    nsfw: bool,
+    round: &RoundWithPreviousAttempts,
     n: usize,
+    canvas: Canvas,
+    labels: &[RgbaImage],
 ) -> Result<RgbaImage, AppError> {
-    let mut images = Vec::with_capacity(n);
-    complement_submissions(ctx, &mut images, attempts, nsfw).await?;
-    complement_draw_this(ctx, &mut images, ir, n).await?;
-    complement_in_construction(ctx, &mut images, ir, n).await?;
-    let image = concat_vertical(&images);
+    let images = extract_images(ctx, ir, round, n, canvas).await?;
+    let image = Grid::new(1, n as u32).compose(&cells(&images, labels));
     Ok(image)
 }
 
+fn cells<'a>(images: &'a [RgbaImage], labels: &'a [RgbaImage]) -> Vec<Cell<'a>> {
+    images
+        .iter()
+        .enumerate()
+        .map(|(i, image)| match labels.get(i) {
+            Some(label) => Cell::labelled(image, label),
+            None => Cell::new(image),
+        })
+        .collect()
+}
+
+async fn extract_images(
+    ctx: &(impl AsRef<Http> + Send + Sync),
+    ir: &ImageRepository,
+    round: &RoundWithPreviousAttempts,
+    n: usize,
+    canvas: Canvas,
+) -> Result<Vec<RgbaImage>, AppError> {
+    let mut images = Vec::with_capacity(n);
+    let rating = round.round.rating;
+    complement_submissions(ctx, &mut images, &round.previous, rating, canvas).await?;
+    complement_draw_this(
+        ctx,
+        &mut images,
+        ir,
+        n,
+        round.round.theme.as_deref(),
+        rating,
+    )
+    .await?;
+    complement_in_construction(ctx, &mut images, ir, n, rating).await?;
+    let images = images
+        .iter()
+        .map(|i| normalize_image(i, canvas.width, canvas.height))
+        .collect();
+    Ok(images)
+}
+
 async fn complement_submissions(
     ctx: &(impl AsRef<Http> + Send + Sync),
     images: &mut Vec<RgbaImage>,
     attempts: &[Record<Attempt<Approved>>],
-    nsfw: bool,
+    rating: u64,
+    canvas: Canvas,
 ) -> Result<(), AppError> {
-    let channel = match nsfw {
-        true => CONFIG.channels.partial_nsfw,
-        false => CONFIG.channels.partial,
-    };
-    for what in attempts.iter().map(|a| a.state.what) {
-        let image = fetch_image_from_channel(ctx, channel, what).await?;
+    let channels = CONFIG.ratings.partial_channels(rating);
+    for attempt in attempts {
+        let image = match attempt.skipped {
+            true => render_skipped(canvas),
+            false => fetch_image_from_channels(ctx, &channels, attempt.state.what).await?,
+        };
         images.push(image);
     }
     Ok(())
@@ -108,13 +156,21 @@ async fn complement_draw_this(
     images: &mut Vec<RgbaImage>,
     ir: &ImageRepository,
     n: usize,
+    theme: Option<&str>,
+    rating: u64,
 ) -> Result<(), AppError> {
     if images.len() < n {
         let required = 1;
-        let assets = ir
-            .random(AssetKind::DrawThis, required)
+        let mut assets = ir
+            .random(AssetKind::DrawThis, theme, rating, required)
             .await
             .map_internal("Missing DrawThis assets")?;
+        if assets.is_empty() && theme.is_some() {
+            assets = ir
+                .random(AssetKind::DrawThis, None, rating, required)
+                .await
+                .map_internal("Missing DrawThis assets")?;
+        }
         let placeholders = required - assets.len() as u32;
         for image in assets.into_iter().map(|a| a.id()) {
             let image = fetch_image_from_channel(ctx, CONFIG.channels.draw_this, image).await?;
@@ -132,11 +188,12 @@ async fn complement_in_construction(
     images: &mut Vec<RgbaImage>,
     ir: &ImageRepository,
     n: usize,
+    rating: u64,
 ) -> Result<(), AppError> {
     if images.len() < n {
         let required = n - images.len();
         let assets = ir
-            .random(AssetKind::InConstruction, required as u32)
+            .random(AssetKind::InConstruction, None, rating, required as u32)
             .await
             .map_internal("Missing InConstruction assets")?;
         let placeholders = required - assets.len();
@@ -159,6 +216,67 @@ pub fn raw_image_to_attachment<'a>(bytes: Vec<u8>) -> AttachmentType<'a> {
     }
 }
 
+const SPOILER_PREFIX: &str = "SPOILER_";
+
+/// Marks an image attachment as a spoiler when `enabled`.
+pub fn spoiler(attachment: AttachmentType<'_>, enabled: bool) -> AttachmentType<'_> {
+    match attachment {
+        AttachmentType::Bytes { data, filename } if enabled => AttachmentType::Bytes {
+            data,
+            filename: format!("{}{}", SPOILER_PREFIX, filename),
+        },
+        attachment => attachment,
+    }
+}
+
+/// Schedules revealing the spoilered partial images of a complete game once the configured delay passes.
+pub async fn reveal_partials(
+    db: &Database,
+    round: &RoundWithPreviousAttempts,
+) -> Result<(), AppError> {
+    let Some(delay) = CONFIG
+        .spoilers
+        .reveal_after
+        .filter(|_| CONFIG.spoilers.partial)
+    else {
+        return Ok(());
+    };
+    let delay = chrono::Duration::from_std(delay).map_internal("Invalid reveal delay")?;
+    let rr: RoundRepository = db.get();
+    rr.schedule_reveal(&round.round.id, Utc::now() + delay)
+        .await
+        .map_internal("Failed to schedule reveal")?;
+    Ok(())
+}
+
+pub async fn reveal_partial(
+    http: &Arc<Http>,
+    channels: &[ChannelId],
+    message: MessageId,
+) -> Result<(), AppError> {
+    let old_message = find_message(http, channels, message).await?;
+    let Some(attachment) = old_message.attachments.first() else {
+        return Ok(());
+    };
+    let Some(filename) = attachment.filename.strip_prefix(SPOILER_PREFIX) else {
+        return Ok(());
+    };
+    let bytes = fetch_raw_image_from_attachment(attachment)
+        .await
+        .map_internal("Failed to fetch image")?;
+    let attachment = AttachmentType::Bytes {
+        data: Cow::Owned(bytes.into()),
+        filename: filename.to_owned(),
+    };
+    old_message
+        .channel_id
+        .edit_message(http, message, |m| {
+            m.remove_all_attachments().attachment(attachment)
+        })
+        .await?;
+    Ok(())
+}
+
 pub fn image_to_attachment<'a>(image: RgbaImage) -> AttachmentType<'a> {
     raw_image_to_attachment(image.to_png())
 }
@@ -175,6 +293,37 @@ pub async fn fetch_image_from_channel(
     Ok(image)
 }
 
+/// Looks a message up in the first of `channels` holding it.
+async fn find_message(
+    ctx: &impl AsRef<Http>,
+    channels: &[ChannelId],
+    message: MessageId,
+) -> Result<Message, AppError> {
+    let (first, rest) = channels.split_first().expect("No channels to search");
+    let error = match first.message(ctx, message).await {
+        Ok(message) => return Ok(message),
+        Err(e) => e,
+    };
+    for channel in rest {
+        if let Ok(message) = channel.message(ctx, message).await {
+            return Ok(message);
+        }
+    }
+    Err(error)?
+}
+
+pub async fn fetch_image_from_channels(
+    ctx: &impl AsRef<Http>,
+    channels: &[ChannelId],
+    image_id: u64,
+) -> Result<RgbaImage, AppError> {
+    let msg = find_message(ctx, channels, MessageId(image_id)).await?;
+    let image = fetch_image_from_attachment(&msg.attachments[0])
+        .await
+        .map_internal("Failed to fetch image")?;
+    Ok(image)
+}
+
 pub fn prompt(round: &RoundWithAttempts<Active>, in_progress: bool) -> String {
     let mode = round.round.mode;
     let round_no = round.round.round_no;
@@ -183,18 +332,127 @@ pub fn prompt(round: &RoundWithAttempts<Active>, in_progress: bool) -> String {
     } else {
         ""
     };
-    let sfw = if round.round.nsfw { "NSFW " } else { "" };
+    let rating = CONFIG.ratings.prefix(round.round.rating);
+    let theme = match &round.round.theme {
+        Some(theme) => format!("Theme: {}.\n", theme),
+        None => "".to_owned(),
+    };
+    let canvas = mode.canvas(round_no);
+    let extensions = match &CONFIG.modes.get(mode).extensions {
+        Some(policy) => format!(
+            "Extended {} of {} times.\n",
+            round.attempt.extensions, policy.max
+        ),
+        None => "".to_owned(),
+    };
     format!(
-        "{}{}{:?} mode round {}.\n{}\nExpiring <t:{}:R>.\nUse `/submit` or `/cancel` to continue.",
+        "{}{}{:?} mode round {}.\n{}{}\nRecommended resolution: {}x{}.\nExpiring <t:{}:R>.\n{}Use `/submit` or `/cancel` to continue.",
         in_progress,
-        sfw,
+        rating,
         mode,
         round_no + 1,
+        theme,
         mode.prompt(round_no),
-        round.attempt.state.until.timestamp()
+        canvas.width,
+        canvas.height,
+        round.attempt.state.until.timestamp(),
+        extensions
     )
 }
 
+/// Picks the session named by `session`, or the only session when none is named.
+pub fn pick_session<T>(
+    mut rounds: Vec<RoundWithAttempts<T>>,
+    session: Option<&str>,
+) -> Result<RoundWithAttempts<T>, AppError> {
+    match session {
+        Some(session) => rounds
+            .into_iter()
+            .find(|r| r.attempt.id.id.to_raw() == session)
+            .map_user("Unknown game session"),
+        None if rounds.len() > 1 => {
+            None.map_user("You have several game sessions, pick one with the `session` option")
+        }
+        None => rounds.pop().map_user("No active game session"),
+    }
+}
+
+pub async fn autocomplete_session(
+    ctx: AppContext<'_>,
+    partial: &str,
+) -> Vec<AutocompleteChoice<String>> {
+    let ur: UserRepository = ctx.data().get();
+    let rr: RoundRepository = ctx.data().get();
+    let Ok(user) = ur.get_user(ctx.author().id.0).await else {
+        return Vec::new();
+    };
+    let Ok(rounds) = rr.get_active_rounds(&user).await else {
+        return Vec::new();
+    };
+    rounds
+        .iter()
+        .map(|round| session_choice(round, ""))
+        .filter(|choice| choice.name.to_lowercase().contains(&partial.to_lowercase()))
+        .collect()
+}
+
+/// Like [autocomplete_session], also offering sessions which expired recently enough to be submitted late.
+pub async fn autocomplete_submission(
+    ctx: AppContext<'_>,
+    partial: &str,
+) -> Vec<AutocompleteChoice<String>> {
+    let ur: UserRepository = ctx.data().get();
+    let rr: RoundRepository = ctx.data().get();
+    let Ok(user) = ur.get_user(ctx.author().id.0).await else {
+        return Vec::new();
+    };
+    let Ok(rounds) = rr.get_active_rounds(&user).await else {
+        return Vec::new();
+    };
+    let since = Utc::now() - CONFIG.late_submissions.grace;
+    let Ok(late) = rr.get_late_rounds(&user, since).await else {
+        return Vec::new();
+    };
+    rounds
+        .iter()
+        .map(|round| session_choice(round, ""))
+        .chain(late.iter().map(|round| session_choice(round, " (late)")))
+        .filter(|choice| choice.name.to_lowercase().contains(&partial.to_lowercase()))
+        .collect()
+}
+
+pub async fn autocomplete_paused_session(
+    ctx: AppContext<'_>,
+    partial: &str,
+) -> Vec<AutocompleteChoice<String>> {
+    let ur: UserRepository = ctx.data().get();
+    let rr: RoundRepository = ctx.data().get();
+    let Ok(user) = ur.get_user(ctx.author().id.0).await else {
+        return Vec::new();
+    };
+    let Ok(rounds) = rr.get_paused_rounds(&user).await else {
+        return Vec::new();
+    };
+    rounds
+        .iter()
+        .map(|round| session_choice(round, ""))
+        .filter(|choice| choice.name.to_lowercase().contains(&partial.to_lowercase()))
+        .collect()
+}
+
+fn session_choice<T>(round: &RoundWithAttempts<T>, suffix: &str) -> AutocompleteChoice<String> {
+    AutocompleteChoice {
+        name: format!(
+            "{}{:?} mode round {}{}",
+            CONFIG.ratings.prefix(round.round.rating),
+            round.round.mode,
+            round.round.round_no + 1,
+            suffix
+        ),
+        value: round.attempt.id.id.to_raw(),
+    }
+}
+
 pub async fn respond_with_prompt(
     rsx: &mut ResponseContext<'_>,
     ctx: &AppContext<'_>,
@@ -212,14 +470,224 @@ pub async fn respond_with_prompt(
     Ok(())
 }
 
+/// Sends the prompt of a round to its player's DMs.
+pub async fn dm_prompt(
+    ctx: &Context,
+    db: &Database,
+    round: &RoundWithAttempts<Active>,
+) -> Result<(), AppError> {
+    let attachment = round.round.mode.render_prompt_image(ctx, round, db).await?;
+    let dms = UserId(round.attempt.who.to_u64())
+        .create_dm_channel(ctx)
+        .await?;
+    dms.send_message(ctx, |m| {
+        m.add_file(attachment).content(prompt(round, false))
+    })
+    .await?;
+    Ok(())
+}
+
+/// Deletes a message, treating an already deleted one as success so the caller can be retried.
+pub async fn delete_message_if_exists(
+    http: impl AsRef<Http>,
+    channel: ChannelId,
+    message: MessageId,
+) -> Result<(), AppError> {
+    match channel.delete_message(http, message).await {
+        Err(serenity::Error::Http(e)) if e.status_code() == Some(StatusCode::NOT_FOUND) => Ok(()),
+        result => Ok(result?),
+    }
+}
+
 pub fn session_destination<S>(round: &RoundWithAttempts<S>) -> ChannelId {
-    match (
-        round.round.round_no == round.round.mode.last_round(),
-        round.round.nsfw,
-    ) {
-        (false, false) => CONFIG.channels.partial,
-        (true, false) => CONFIG.channels.complete,
-        (false, true) => CONFIG.channels.partial_nsfw,
-        (true, true) => CONFIG.channels.complete_nsfw,
+    let rating = CONFIG.ratings.get(round.round.rating);
+    match round.round.round_no == round.round.mode.last_round() {
+        false => rating.partial,
+        true => rating.complete,
+    }
+}
+
+/// Next round of a chain, with competitive rounds taking their draft count from the configuration.
+pub fn next_round(round: &Round) -> Round {
+    let multiplex = CONFIG.modes.multiplex(round.mode, round.round_no + 1);
+    round.forward(Some(multiplex))
+}
+
+/// Forwards an approved attempt to the next round.
+/// Drafts of competitive rounds are instead put up for a vote, which opens once every draft is approved.
+pub async fn forward_round(
+    ctx: &Context,
+    db: &Database,
+    round: &RoundWithAttempts<Approved>,
+) -> Result<(), AppError> {
+    // Party chains are forwarded together by the party runner once the shared deadline passes
+    if round.round.party.is_some() {
+        return Ok(());
+    }
+
+    let rr: RoundRepository = db.get();
+    let Some(competitive) = CONFIG
+        .modes
+        .competitive(round.round.mode, round.round.round_no)
+    else {
+        let next = rr
+            .forward_complete_round(&round.round, &round.attempt, next_round(&round.round))
+            .await
+            .map_internal("Failed to forward round")?;
+        if let Err(e) = notify_subscribers(ctx, db, &next).await {
+            error!(error = %e, round = %next.round.id, "Failed to notify subscribers");
+        }
+        return Ok(());
+    };
+
+    let channel = session_destination(round);
+    let vote = ReactionType::Unicode(CONFIG.reactions.vote.clone());
+    channel
+        .create_reaction(ctx, MessageId(round.attempt.state.what), vote)
+        .await?;
+
+    let until = Utc::now() + competitive.voting;
+    let voting = rr
+        .try_open_voting(&round.round, until)
+        .await
+        .map_internal("Failed to open voting")?;
+    if voting.is_some() {
+        channel
+            .send_message(ctx, |m| {
+                m.content(format!(
+                    "Voting for {:?} mode round {} is open until <t:{}:f>.\nReact with {} to the draft that should continue the game.",
+                    round.round.mode,
+                    round.round.round_no + 1,
+                    until.timestamp(),
+                    CONFIG.reactions.vote
+                ))
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+/// Tells subscribers about a newly opened round, joining it on behalf of those who asked to.
+/// Subscribers the mode's fairness rules keep out of the round are left waiting for another one.
+pub async fn notify_subscribers(
+    ctx: &Context,
+    db: &Database,
+    next: &RoundWithPreviousAttempts,
+) -> Result<(), AppError> {
+    let sr: SubscriptionRepository = db.get();
+    let subscriptions = sr
+        .get_matching(&next.round)
+        .await
+        .map_internal("Failed to get subscriptions")?;
+    for subscription in subscriptions {
+        if !may_join(next, &subscription.user) {
+            continue;
+        }
+        if let Err(e) = notify_subscriber(ctx, db, &next.round, &subscription).await {
+            warn!(error = %e, user = %subscription.user, "Failed to notify subscriber");
+        }
+    }
+    Ok(())
+}
+
+/// Whether the mode's fairness rules let the user join the round.
+fn may_join(next: &RoundWithPreviousAttempts, user: &Thing) -> bool {
+    let fairness = &CONFIG.modes.get(next.round.mode).fairness;
+    let followed = next.previous.last().is_some_and(|a| &a.who == user);
+    let contributions = next.previous.iter().filter(|a| &a.who == user).count() as u64;
+    let followed = fairness.no_self_follow && followed;
+    let saturated = fairness
+        .max_contributions
+        .is_some_and(|max| contributions >= max);
+    !followed && !saturated
+}
+
+/// Subscriptions are used up once the subscriber was told, auto-claiming ones once they joined a round.
+async fn notify_subscriber(
+    ctx: &Context,
+    db: &Database,
+    round: &Record<Round>,
+    subscription: &Record<Subscription>,
+) -> Result<(), AppError> {
+    let sr: SubscriptionRepository = db.get();
+    if subscription.auto_claim {
+        let ur: UserRepository = db.get();
+        let user = ur
+            .get_user(subscription.user.to_u64())
+            .await
+            .map_internal("Failed to get user")?;
+        let Some(claimed) = claim_for_subscriber(db, &user, round, subscription).await? else {
+            return Ok(());
+        };
+        if let Err(e) = dm_prompt(ctx, db, &claimed).await {
+            let ar: AttemptRepository = db.get();
+            ar.release_active_attempt(&user, &claimed.attempt.id)
+                .await
+                .map_internal("Failed to release claimed round")?;
+            return Err(e);
+        }
+        sr.take(&subscription.id)
+            .await
+            .map_internal("Failed to take subscription")?;
+        return Ok(());
+    }
+    let dms = UserId(subscription.user.to_u64())
+        .create_dm_channel(ctx)
+        .await?;
+    dms.send_message(ctx, |m| {
+        m.content(format!(
+            "A {:?} mode game is waiting for round {}.\nUse `/start` to play it.",
+            round.mode,
+            round.round_no + 1
+        ))
+    })
+    .await?;
+    sr.take(&subscription.id)
+        .await
+        .map_internal("Failed to take subscription")?;
+    Ok(())
+}
+
+/// Joins a matching round for the subscriber, unless they already play this mode or as many games as they can.
+async fn claim_for_subscriber(
+    db: &Database,
+    user: &Record<User>,
+    round: &Record<Round>,
+    subscription: &Record<Subscription>,
+) -> Result<Option<RoundWithAttempts<Active>>, AppError> {
+    let ar: AttemptRepository = db.get();
+    let rr: RoundRepository = db.get();
+    let active = rr
+        .get_active_rounds(user)
+        .await
+        .map_internal("Failed to find existing sessions")?;
+    let paused = rr
+        .get_paused_rounds(user)
+        .await
+        .map_internal("Failed to find paused sessions")?;
+    let playing = active.iter().any(|r| r.round.mode == round.mode)
+        || paused.iter().any(|r| r.round.mode == round.mode);
+    if playing || (active.len() + paused.len()) as u64 >= CONFIG.sessions.max_active {
+        return Ok(None);
+    }
+
+    let time_limit = time_limit_for(&ar, user, round.mode, round.round_no).await?;
+    let claimed = rr
+        .with_allocation(CONFIG.modes.get(round.mode).allocation)
+        .with_skips_since(Utc::now() - CONFIG.skips.window)
+        .attempt_existing_round(
+            user,
+            round.mode,
+            subscription.max_rating,
+            None,
+            round.round_no,
+            time_limit,
+            &CONFIG.modes.get(round.mode).fairness,
+        )
+        .await;
+    match claimed {
+        Ok(claimed) => Ok(Some(claimed)),
+        Err(DbError::NotFound) => Ok(None),
+        Err(e) => Err(e).map_internal("Failed to claim round"),
     }
 }
@@ -0,0 +1,104 @@
+use crate::app::{
+    config::CONFIG,
+    error::ConvertError,
+    util::{next_round, notify_subscribers, session_destination},
+};
+
+use super::error::AppError;
+use chrono::Utc;
+use gartic_on_demand::services::{
+    database::{
+        round::{ClosedVoting, Round, RoundRepository},
+        Database, Record,
+    },
+    provider::Provider,
+};
+use poise::serenity_prelude::MessageId;
+use serde::Deserialize;
+use serde_with::{serde_as, DurationSeconds};
+use serenity::prelude::Context;
+use tracing::{error, info};
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct VoteCloserConfig {
+    #[serde_as(as = "DurationSeconds<u64>")]
+    check_interval: std::time::Duration,
+}
+
+pub struct VoteCloser {
+    db: Database,
+    rr: RoundRepository,
+    ctx: Context,
+}
+
+impl VoteCloser {
+    pub fn new(db: Database, ctx: Context) -> Self {
+        Self {
+            rr: db.get(),
+            db,
+            ctx,
+        }
+    }
+
+    pub async fn run(mut self) {
+        loop {
+            if let Err(e) = self.run_internal().await {
+                error!(error = %e, "Vote closer error");
+            }
+        }
+    }
+
+    pub async fn run_internal(&mut self) -> Result<(), AppError> {
+        info!("Starting vote closer");
+        loop {
+            self.loop_body().await?;
+        }
+    }
+
+    async fn loop_body(&mut self) -> Result<(), AppError> {
+        info!("Closing finished votings");
+        let rounds = self
+            .rr
+            .get_expired_votings(Utc::now())
+            .await
+            .map_internal("Failed to get finished votings")?;
+
+        for round in rounds {
+            if let Err(e) = self.close(&round).await {
+                error!(error = %e, round = %round.id, "Failed to close voting");
+            }
+        }
+
+        tokio::time::sleep(CONFIG.vote_closer.check_interval).await;
+        Ok(())
+    }
+
+    async fn close(&self, round: &Record<Round>) -> Result<(), AppError> {
+        let closed = self
+            .rr
+            .close_voting(round, next_round(round))
+            .await
+            .map_internal("Failed to close voting")?;
+        let Some(ClosedVoting { winner, next }) = closed else {
+            info!(round = %round.id, "Reopened voting without approved drafts");
+            return Ok(());
+        };
+        if let Err(e) = notify_subscribers(&self.ctx, &self.db, &next).await {
+            error!(error = %e, round = %next.round.id, "Failed to notify subscribers");
+        }
+
+        let channel = session_destination(&winner);
+        let what = MessageId(winner.attempt.state.what);
+        channel
+            .send_message(&self.ctx, |m| {
+                m.reference_message((channel, what)).content(format!(
+                    "Voting closed, this draft continues {:?} mode game in round {}.",
+                    winner.round.mode,
+                    winner.round.round_no + 2
+                ))
+            })
+            .await?;
+        Ok(())
+    }
+}
@@ -5,8 +5,15 @@ use app::{
     config::CONFIG,
     error::AppError,
     expiry_notifier::ExpiryNotifier,
-    handlers::{accept_submission::AcceptSubmission, remove_asset::RemoveAsset, AssetHandler},
+    handlers::{
+        accept_submission::AcceptSubmission, cast_vote::CastVote, rate_submission::RateSubmission,
+        remove_asset::RemoveAsset, report_submission::ReportSubmission,
+        resolve_appeal::ResolveAppeal, resolve_report::ResolveReport, AssetHandler,
+    },
+    party_runner::PartyRunner,
+    spoiler_revealer::SpoilerRevealer,
     stats_printer::StatsPrinter,
+    vote_closer::VoteCloser,
     AppData,
 };
 use gartic_on_demand::services::{provider::Provider, status_update::status_update_pair};
@@ -51,10 +58,16 @@ async fn main() {
             move |ctx: &Context, _ready: &Ready, framework: &Framework<AppData, AppError>| {
                 let stats_printer = StatsPrinter::new(app_data.get(), waiter, ctx.clone());
                 let expiry_notifier = ExpiryNotifier::new(app_data.get(), ctx.clone());
+                let vote_closer = VoteCloser::new(app_data.get(), ctx.clone());
+                let party_runner = PartyRunner::new(app_data.get(), ctx.clone());
+                let spoiler_revealer = SpoilerRevealer::new(app_data.get(), ctx.clone());
                 Box::pin(async move {
                     poise::builtins::register_globally(ctx, &framework.options().commands).await?;
                     spawn(stats_printer.run());
                     spawn(expiry_notifier.run());
+                    spawn(vote_closer.run());
+                    spawn(party_runner.run());
+                    spawn(spoiler_revealer.run());
                     Ok(app_data)
                 })
             },
@@ -76,7 +89,15 @@ fn event_handler<'a>(
     let event = event.clone();
     let data = data.clone();
     Box::pin(async move {
-        let handlers: &[&dyn AssetHandler] = &[&RemoveAsset, &AcceptSubmission];
+        let handlers: &[&dyn AssetHandler] = &[
+            &RemoveAsset,
+            &AcceptSubmission,
+            &CastVote,
+            &RateSubmission,
+            &ReportSubmission,
+            &ResolveReport,
+            &ResolveAppeal,
+        ];
         for handler in handlers {
             if let Err(e) = handler.handle(&ctx, &event, fcx, &data).await {
                 error!(error = %e, handler = ?handler, "Error in handler");
@@ -98,8 +119,17 @@ fn options() -> poise::FrameworkOptions<AppData, AppError> {
             commands::random_attributes::random_attributes(),
             commands::purge::purge(),
             commands::extend::extend(),
+            commands::pause::pause(),
+            commands::resume::resume(),
             commands::reroll::reroll(),
             commands::notify::notify(),
+            commands::theme::theme(),
+            commands::lobby::lobby(),
+            commands::party::party(),
+            commands::admin::admin(),
+            commands::appeal::appeal(),
+            commands::reliability::reliability(),
+            commands::subscribe::subscribe(),
         ],
         on_error: |error| Box::pin(on_error(error)),
         event_handler,
@@ -0,0 +1,115 @@
+use super::{user::User, BetterCheck, Database, DbResult, MapToNotFound, Record};
+use crate::services::provider::Provider;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(tag = "type")]
+pub enum AppealStatus {
+    #[default]
+    Open,
+    Granted {
+        who: Thing,
+        when: DateTime<Utc>,
+    },
+    Denied {
+        who: Thing,
+        when: DateTime<Utc>,
+    },
+}
+
+/// Appeal of a rejected attempt, identified by its message in the appeals channel.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Appeal {
+    pub attempt: Thing,
+    pub appellant: Thing,
+    pub rejected_by: Thing,
+    pub message: Option<String>,
+    pub status: AppealStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct AppealRepository {
+    db: Database,
+}
+
+impl<T> Provider<AppealRepository> for T
+where
+    T: Provider<Database>,
+{
+    fn get(&self) -> AppealRepository {
+        AppealRepository { db: self.get() }
+    }
+}
+
+impl AppealRepository {
+    pub const TABLE: &str = "appeal";
+
+    pub async fn create(
+        &self,
+        queued: u64,
+        appellant: &Record<User>,
+        attempt: &Thing,
+        rejected_by: &Thing,
+        message: Option<String>,
+    ) -> DbResult<Record<Appeal>> {
+        let appeal = Appeal {
+            attempt: attempt.clone(),
+            appellant: appellant.id.clone(),
+            rejected_by: rejected_by.clone(),
+            message,
+            status: AppealStatus::Open,
+            created_at: Utc::now(),
+        };
+        let appeal = self
+            .db
+            .create::<Option<Record<Appeal>>>((Self::TABLE, queued))
+            .content(&appeal)
+            .await?
+            .found()?;
+        Ok(appeal)
+    }
+
+    /// Attempts can only be appealed once, whatever the outcome.
+    pub async fn is_appealed(&self, attempt: &Thing) -> DbResult<bool> {
+        let mut result = self
+            .db
+            .query("select value id from appeal where attempt is $attempt")
+            .bind(("attempt", attempt))
+            .await?
+            .better_check()?;
+        let appeals = result.take::<Vec<Thing>>(0)?;
+        Ok(!appeals.is_empty())
+    }
+
+    pub async fn get_open(&self, queued: u64) -> DbResult<Record<Appeal>> {
+        self.db
+            .select::<Option<Record<Appeal>>>((Self::TABLE, queued))
+            .await?
+            .filter(|a| matches!(a.status, AppealStatus::Open))
+            .found()
+    }
+
+    pub async fn resolve(
+        &self,
+        appeal: &Record<Appeal>,
+        moderator: &Record<User>,
+        granted: bool,
+    ) -> DbResult<Record<Appeal>> {
+        let (who, when) = (moderator.id.clone(), Utc::now());
+        let status = match granted {
+            true => AppealStatus::Granted { who, when },
+            false => AppealStatus::Denied { who, when },
+        };
+        let mut result = self
+            .db
+            .query("update only $appeal set status = $status")
+            .bind(("appeal", &appeal.id))
+            .bind(("status", status))
+            .await?
+            .better_check()?;
+        let appeal = result.take::<Option<Record<Appeal>>>(0)?.found()?;
+        Ok(appeal)
+    }
+}
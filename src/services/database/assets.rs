@@ -16,15 +16,20 @@ pub struct Asset {
     pub author: u64,
     pub submitter: u64,
     pub created_at: DateTime<Utc>,
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub rating: u64,
 }
 
 impl Asset {
-    pub fn new(kind: AssetKind, uid: u64, aid: u64) -> Self {
+    pub fn new(kind: AssetKind, uid: u64, aid: u64, theme: Option<String>, rating: u64) -> Self {
         Self {
             kind,
             author: uid,
             submitter: aid,
             created_at: Utc::now(),
+            theme,
+            rating,
         }
     }
 }
@@ -55,12 +60,21 @@ impl ImageRepository {
         Ok(())
     }
 
-    pub async fn random(&self, kind: AssetKind, n: u32) -> DbResult<Vec<Record<Asset>>> {
-        let query = r#"SELECT * FROM assets WHERE kind = $kind ORDER BY rand() LIMIT $limit"#;
+    /// Random assets allowed in rounds rated `max_rating`, of any theme when none is provided.
+    pub async fn random(
+        &self,
+        kind: AssetKind,
+        theme: Option<&str>,
+        max_rating: u64,
+        n: u32,
+    ) -> DbResult<Vec<Record<Asset>>> {
+        let query = r#"SELECT * FROM assets WHERE kind = $kind AND ($theme IS NONE OR theme IS $theme) AND rating <= $max_rating ORDER BY rand() LIMIT $limit"#;
         let mut result = self
             .db
             .query(query)
             .bind(("kind", kind))
+            .bind(("theme", theme))
+            .bind(("max_rating", max_rating))
             .bind(("limit", n))
             .await?;
         let images = result.take::<Vec<Record<Asset>>>(0)?;
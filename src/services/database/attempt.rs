@@ -1,10 +1,15 @@
-use super::{round::RoundWithAttempts, user::User, Record};
+use super::{
+    audit::{AuditAction, AuditEntry},
+    round::{Fairness, Round, RoundWithAttempts},
+    user::User,
+    Record,
+};
 use crate::services::{
-    database::{Database, DbResult, MapToNotFound},
+    database::{BetterCheck, Database, DbResult, MapToNotFound},
     provider::Provider,
 };
 use chrono::{DateTime, Duration, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use surrealdb::sql::Thing;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,6 +17,14 @@ pub struct Active {
     pub until: DateTime<Utc>,
 }
 
+/// Frozen active attempt, which resumes with `remaining` seconds left and expires when not resumed by `until`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Paused {
+    pub since: DateTime<Utc>,
+    pub remaining: i64,
+    pub until: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Cancelled {
     pub when: DateTime<Utc>,
@@ -47,6 +60,53 @@ pub struct Rejected {
     pub what: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TakenDown {
+    pub when: DateTime<Utc>,
+    pub who: Thing,
+    pub what: u64,
+}
+
+/// Outcomes of a user's most recent submissions, taken down submissions count as rejected.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReviewRecord {
+    pub approved: u64,
+    pub rejected: u64,
+}
+
+impl ReviewRecord {
+    pub fn rejection_rate(&self) -> f64 {
+        match self.approved + self.rejected {
+            0 => 0.0,
+            total => self.rejected as f64 / total as f64,
+        }
+    }
+}
+
+/// Finished attempts of a user, approvals against attempts they cancelled or let expire, rerolls aside.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReliabilityRecord {
+    pub approved: u64,
+    /// Approved attempts submitted during the grace period, included in `approved`.
+    pub late: u64,
+    pub expired: u64,
+    pub cancelled: u64,
+}
+
+impl ReliabilityRecord {
+    pub fn total(&self) -> u64 {
+        self.approved + self.expired + self.cancelled
+    }
+
+    /// Share of approved attempts, users without any are considered reliable.
+    pub fn score(&self) -> f64 {
+        match self.total() {
+            0 => 1.0,
+            total => self.approved as f64 / total as f64,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
 pub enum AttemptState {
@@ -54,6 +114,10 @@ pub enum AttemptState {
         #[serde(flatten)]
         inner: Active,
     },
+    Paused {
+        #[serde(flatten)]
+        inner: Paused,
+    },
     Cancelled {
         #[serde(flatten)]
         inner: Cancelled,
@@ -78,6 +142,10 @@ pub enum AttemptState {
         #[serde(flatten)]
         inner: Rejected,
     },
+    TakenDown {
+        #[serde(flatten)]
+        inner: TakenDown,
+    },
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,6 +154,25 @@ pub struct Attempt<T> {
     pub who: Thing,
     pub state: T,
     pub created_at: DateTime<Utc>,
+    /// Submitted during the grace period after expiring.
+    #[serde(default)]
+    pub late: bool,
+    #[serde(default)]
+    pub extensions: u64,
+    #[serde(default)]
+    pub pauses: u64,
+    /// Stands in as approved for a missed party turn, without an image.
+    #[serde(default)]
+    pub skipped: bool,
+    /// Cancelled by `/reroll`, which still skips the round but is no abandonment.
+    #[serde(default)]
+    pub rerolled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Outcome {
+    state: String,
+    late: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -111,6 +198,7 @@ impl AttemptRepository {
     pub async fn extend_active_attempt(
         &self,
         user: &Record<User>,
+        attempt: &Thing,
         time_limit: Duration,
     ) -> DbResult<RoundWithAttempts<Active>> {
         let now = Utc::now();
@@ -121,8 +209,9 @@ impl AttemptRepository {
         };
         let mut result = self
             .db
-            .query("let $attempt = update only attempt set state = $state where in is $user and state.type = $state_type")
+            .query("let $attempt = update only $active set state = $state, extensions += 1 where in is $user and state.type = $state_type")
             .bind(("state_type", "Active"))
+            .bind(("active", attempt))
             .bind(("user", &user.id))
             .bind(("state", state))
             .query("fn::get_round_with_attempt($attempt)")
@@ -136,6 +225,25 @@ impl AttemptRepository {
     pub async fn cancel_active_attempt(
         &self,
         user: &Record<User>,
+        attempt: &Thing,
+    ) -> DbResult<Record<Attempt<Cancelled>>> {
+        self.end_active_attempt(user, attempt, false).await
+    }
+
+    /// Cancels the attempt to swap its round for another, which is not held against the user's reliability.
+    pub async fn reroll_active_attempt(
+        &self,
+        user: &Record<User>,
+        attempt: &Thing,
+    ) -> DbResult<Record<Attempt<Cancelled>>> {
+        self.end_active_attempt(user, attempt, true).await
+    }
+
+    async fn end_active_attempt(
+        &self,
+        user: &Record<User>,
+        attempt: &Thing,
+        rerolled: bool,
     ) -> DbResult<Record<Attempt<Cancelled>>> {
         let now = Utc::now();
         let state = AttemptState::Cancelled {
@@ -143,10 +251,12 @@ impl AttemptRepository {
         };
         let mut result = self
             .db
-            .query("update only attempt set state = $state where in is $user and state.type = $state_type")
+            .query("update only $active set state = $state, rerolled = $rerolled where in is $user and state.type = $state_type")
             .bind(("state_type", "Active"))
+            .bind(("active", attempt))
             .bind(("user", &user.id))
             .bind(("state", state))
+            .bind(("rerolled", rerolled))
             .await?;
         let attempt = result
             .take::<Option<Record<Attempt<Cancelled>>>>(0)?
@@ -154,6 +264,23 @@ impl AttemptRepository {
         Ok(attempt)
     }
 
+    /// Drops an active attempt as if it never happened, freeing its round without counting as a skip.
+    pub async fn release_active_attempt(
+        &self,
+        user: &Record<User>,
+        attempt: &Thing,
+    ) -> DbResult<()> {
+        self.db
+            .query("delete $active where in is $user and state.type = $state_type")
+            .bind(("state_type", "Active"))
+            .bind(("active", attempt))
+            .bind(("user", &user.id))
+            .await?
+            .better_check()?;
+        Ok(())
+    }
+
+    /// Expires active attempts past their time limit and paused attempts past their maximum pause.
     pub async fn expire_active_attempts(&self) -> DbResult<Vec<Record<()>>> {
         let now = Utc::now();
         let state = AttemptState::Expired {
@@ -161,8 +288,8 @@ impl AttemptRepository {
         };
         let mut result = self
             .db
-            .query("update attempt set state = $state where state.type = $state_type and state.until < $now")
-            .bind(("state_type", "Active"))
+            .query("update attempt set state = $state where state.type in $state_types and state.until < $now")
+            .bind(("state_types", ["Active", "Paused"]))
             .bind(("state", state))
             .bind(("now", now))
             .await?;
@@ -170,9 +297,94 @@ impl AttemptRepository {
         Ok(attempt)
     }
 
+    /// Freezes the remaining time of the active attempt, keeping its round allocated for at most `max_pause`.
+    pub async fn pause_active_attempt(
+        &self,
+        user: &Record<User>,
+        active: &Record<Attempt<Active>>,
+        max_pause: Duration,
+    ) -> DbResult<Record<Attempt<Paused>>> {
+        let now = Utc::now();
+        let state = AttemptState::Paused {
+            inner: Paused {
+                since: now,
+                remaining: (active.state.until - now).num_seconds().max(0),
+                until: now + max_pause,
+            },
+        };
+        let mut result = self
+            .db
+            .query("update only $attempt set state = $state, pauses += 1 where in is $user and state.type is $active_type")
+            .bind(("active_type", "Active"))
+            .bind(("attempt", &active.id))
+            .bind(("user", &user.id))
+            .bind(("state", state))
+            .await?
+            .better_check()?;
+        let attempt = result.take::<Option<Record<Attempt<Paused>>>>(0)?.found()?;
+        Ok(attempt)
+    }
+
+    /// Resumes the paused attempt with the time it had left.
+    /// Refused while the user holds `max_active` active attempts or already plays the attempt's mode.
+    pub async fn resume_paused_attempt(
+        &self,
+        user: &Record<User>,
+        attempt: &Thing,
+        max_active: u64,
+    ) -> DbResult<RoundWithAttempts<Active>> {
+        let mut result = self
+            .db
+            .query("select * from $attempt where in is $user and state.type is $state_type")
+            .bind(("attempt", attempt))
+            .bind(("state_type", "Paused"))
+            .bind(("user", &user.id))
+            .await?
+            .better_check()?;
+        let paused = result
+            .take::<Vec<Record<Attempt<Paused>>>>(0)?
+            .pop()
+            .found()?;
+        let state = AttemptState::Active {
+            inner: Active {
+                until: Utc::now() + Duration::seconds(paused.state.remaining),
+            },
+        };
+        let mut result = self
+            .db
+            .query("begin")
+            .query("let $busy = select value out.mode from attempt where in is $user and state.type is $active_type")
+            .bind(("active_type", "Active"))
+            .bind(("user", &user.id))
+            .query("let $mode = select value out.mode from only $paused")
+            .query(
+                r"
+                let $attempt = if array::len($busy) < $max_active and $mode notinside $busy {
+                    return update attempt set state = $state where id is $paused and state.type is $state_type;
+                } else {
+                    return [];
+                }
+                ",
+            )
+            .bind(("state_type", "Paused"))
+            .bind(("paused", &paused.id))
+            .bind(("max_active", max_active))
+            .bind(("state", state))
+            .query("let $attempt = array::first($attempt)")
+            .query("commit")
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .await?
+            .better_check()?;
+        let attempt = result
+            .take::<Option<RoundWithAttempts<Active>>>(4)?
+            .found()?;
+        Ok(attempt)
+    }
+
     pub async fn upload_active_attempt(
         &self,
         user: &Record<User>,
+        attempt: &Thing,
     ) -> DbResult<RoundWithAttempts<Uploading>> {
         let now = Utc::now();
         let state = AttemptState::Uploading {
@@ -180,7 +392,8 @@ impl AttemptRepository {
         };
         let mut result = self
             .db
-            .query("let $attempt = update only attempt set state = $state where in is $user and state.type = $state_type")
+            .query("let $attempt = update only $active set state = $state where in is $user and state.type = $state_type")
+            .bind(("active", attempt))
             .bind(("state_type", "Active"))
             .bind(("user", &user.id))
             .bind(("state", state))
@@ -192,9 +405,71 @@ impl AttemptRepository {
         Ok(attempt)
     }
 
+    /// Uploads to the user's `attempt` if it expired after `since`, as long as nobody else allocated its round.
+    pub async fn upload_late_attempt(
+        &self,
+        user: &Record<User>,
+        attempt: &Thing,
+        since: DateTime<Utc>,
+    ) -> DbResult<RoundWithAttempts<Uploading>> {
+        let now = Utc::now();
+        let state = AttemptState::Uploading {
+            inner: Uploading { since: now },
+        };
+        let mut result = self
+            .db
+            .query("begin")
+            .query(
+                r"
+                let $expired = select * from $expired
+                    where in is $user
+                    and state.type is $state_type
+                    and state.when > $since
+                ",
+            )
+            .bind(("expired", attempt))
+            .bind(("state_type", "Expired"))
+            .bind(("user", &user.id))
+            .bind(("since", since))
+            .query("let $expired = array::first($expired)")
+            .query("let $round = $expired.out")
+            .query(
+                r"
+                let $free = if $expired is not none {
+                    return select value
+                        status.type is $open
+                        and flagged is not true
+                        and array::len(<-(attempt where state.type in $allocating_state_types)) < multiplex
+                        from only $round;
+                }
+                ",
+            )
+            .bind(("open", "Open"))
+            .query(
+                r"
+                let $attempt = if $free {
+                    return update attempt set state = $state, late = true where id is $expired.id;
+                } else {
+                    return [];
+                }
+                ",
+            )
+            .bind(("state", state))
+            .query("let $attempt = array::first($attempt)")
+            .query("commit")
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .await?
+            .better_check()?;
+        let attempt = result
+            .take::<Option<RoundWithAttempts<Uploading>>>(6)?
+            .found()?;
+        Ok(attempt)
+    }
+
     pub async fn approve_uploaded_attempt(
         &self,
         user: &Record<User>,
+        attempt: &Thing,
         image_id: u64,
     ) -> DbResult<RoundWithAttempts<Approved>> {
         let now = Utc::now();
@@ -207,8 +482,9 @@ impl AttemptRepository {
         };
         let mut result = self
             .db
-            .query("let $attempt = update only attempt set state = $state where in is $user and state.type = $state_type")
+            .query("let $attempt = update only $uploading set state = $state where in is $user and state.type = $state_type")
             .bind(("state_type", "Uploading"))
+            .bind(("uploading", attempt))
             .bind(("user", &user.id))
             .bind(("state", state))
             .query("fn::get_round_with_attempt($attempt)")
@@ -222,6 +498,7 @@ impl AttemptRepository {
     pub async fn moderate_uploaded_attempt(
         &self,
         user: &Record<User>,
+        attempt: &Thing,
         image_id: u64,
     ) -> DbResult<Record<Attempt<Pending>>> {
         let now = Utc::now();
@@ -233,8 +510,9 @@ impl AttemptRepository {
         };
         let mut result = self
             .db
-            .query("update only attempt set state = $state where in is $user and state.type = $state_type")
+            .query("update only $uploading set state = $state where in is $user and state.type = $state_type")
             .bind(("state_type", "Uploading"))
+            .bind(("uploading", attempt))
             .bind(("user", &user.id))
             .bind(("state", state))
             .await?;
@@ -333,185 +611,1309 @@ impl AttemptRepository {
         Ok(attempt)
     }
 
-    pub async fn get_active_between(
+    /// The user's latest rejected attempt, if it was rejected after `since`.
+    pub async fn get_rejected_attempt_since(
         &self,
-        after: DateTime<Utc>,
-        until: DateTime<Utc>,
-    ) -> DbResult<Vec<Record<Attempt<Active>>>> {
+        user: &Record<User>,
+        since: DateTime<Utc>,
+    ) -> DbResult<RoundWithAttempts<Rejected>> {
         let mut result = self
             .db
-            .query("select * from attempt where state.type = $state_type and state.until > $after and state.until <= $until")
-            .bind(("state_type", "Active"))
-            .bind(("after", after))
-            .bind(("until", until))
-            .await?;
-        let attempts = result.take::<Vec<Record<Attempt<Active>>>>(0)?;
-        Ok(attempts)
+            .query(
+                r"
+                let $attempt = select * from attempt
+                    where in is $user
+                    and state.type is $state_type
+                    and state.when > $since
+                    order by created_at desc
+                    limit 1
+                ",
+            )
+            .bind(("state_type", "Rejected"))
+            .bind(("user", &user.id))
+            .bind(("since", since))
+            .query("let $attempt = array::first($attempt)")
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .await?
+            .better_check()?;
+        let attempt = result
+            .take::<Option<RoundWithAttempts<Rejected>>>(2)?
+            .found()?;
+        Ok(attempt)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use chrono::{Duration, Utc};
 
-    use super::AttemptRepository;
-    use crate::services::{
-        database::{round::RoundRepository, tests::db, user::UserRepository},
-        gamemodes::Mode,
-        provider::Provider,
-    };
-
-    async fn setup() -> (UserRepository, RoundRepository, AttemptRepository) {
-        let db = db().await;
-        (db.get(), db.get(), db.get())
+    pub async fn get_rejected_attempt(
+        &self,
+        attempt: &Thing,
+    ) -> DbResult<RoundWithAttempts<Rejected>> {
+        let mut result = self
+            .db
+            .query("let $attempt = select * from attempt where id is $rejected and state.type is $state_type")
+            .bind(("state_type", "Rejected"))
+            .bind(("rejected", attempt))
+            .query("let $attempt = array::first($attempt)")
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .await?
+            .better_check()?;
+        let attempt = result
+            .take::<Option<RoundWithAttempts<Rejected>>>(2)?
+            .found()?;
+        Ok(attempt)
     }
 
-    #[tokio::test]
-    async fn extend_attempt_to_avoid_expiration() {
-        let (users, rounds, sut) = setup().await;
-        let user = users.create_or_update_user(0, "").await.unwrap();
-        rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
-            .await
-            .unwrap();
-
-        sut.extend_active_attempt(&user, Duration::seconds(60 * 60))
-            .await
-            .unwrap();
-
-        let expired = sut.expire_active_attempts().await.unwrap();
-        assert!(expired.is_empty());
+    /// Approves a rejected attempt after a successful appeal.
+    /// Returns whether others claimed the round since the rejection, in which case the round is marked conflicting.
+    pub async fn restore_rejected_attempt(
+        &self,
+        moderator: &Record<User>,
+        attempt: &Thing,
+        image_id: u64,
+    ) -> DbResult<(RoundWithAttempts<Approved>, bool)> {
+        let now = Utc::now();
+        let state = AttemptState::Approved {
+            inner: Approved {
+                when: now,
+                who: moderator.id.clone(),
+                what: image_id,
+            },
+        };
+        let mut result = self
+            .db
+            .query("begin")
+            .query("let $attempt = update $rejected set state = $state where state.type is $state_type")
+            .bind(("state_type", "Rejected"))
+            .bind(("rejected", attempt))
+            .bind(("state", state))
+            .query("let $attempt = array::first($attempt)")
+            .query("let $round = $attempt.out")
+            .query(
+                r"
+                let $conflicting = if $attempt is not none {
+                    return select value
+                        status.type is not $open
+                        or array::len(<-(attempt where state.type in $allocating_state_types and id is not $attempt.id)) >= multiplex
+                        from only $round;
+                }
+                ",
+            )
+            .bind(("open", "Open"))
+            .query("if $conflicting { update $round set conflicting = true }")
+            .query("commit")
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .query("$conflicting")
+            .await?
+            .better_check()?;
+        let attempt = result
+            .take::<Option<RoundWithAttempts<Approved>>>(5)?
+            .found()?;
+        let conflicting = result.take::<Option<bool>>(6)?.unwrap_or_default();
+        Ok((attempt, conflicting))
     }
 
-    #[tokio::test]
-    async fn expire_one_out_of_two() {
-        let (users, rounds, sut) = setup().await;
-        let user = users.create_or_update_user(0, "").await.unwrap();
-        rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
-            .await
-            .unwrap();
-        rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(60 * 60))
-            .await
-            .unwrap();
+    pub async fn get_approved_attempt(
+        &self,
+        image_id: u64,
+    ) -> DbResult<RoundWithAttempts<Approved>> {
+        let mut result = self
+            .db
+            .query("let $attempt = select * from only attempt where state.what is $what and state.type = $state_type")
+            .bind(("state_type", "Approved"))
+            .bind(("what", image_id))
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .await?;
+        let attempt = result
+            .take::<Option<RoundWithAttempts<Approved>>>(1)?
+            .found()?;
+        Ok(attempt)
+    }
 
-        let expired = sut.expire_active_attempts().await.unwrap();
+    /// Takes down an approved attempt. Taken down attempts do not allocate their round, so it reopens.
+    /// Chains which already advanced past the attempt are flagged instead, returning every flagged round.
+    pub async fn take_down_approved_attempt(
+        &self,
+        moderator: &Record<User>,
+        image_id: u64,
+    ) -> DbResult<Vec<Record<Round>>> {
+        let now = Utc::now();
+        let state = AttemptState::TakenDown {
+            inner: TakenDown {
+                when: now,
+                who: moderator.id.clone(),
+                what: image_id,
+            },
+        };
+        let mut result = self
+            .db
+            .query("begin")
+            .query(
+                r"
+                let $attempt = update attempt
+                    set state = $state
+                    where state.type is $state_type
+                    and state.what is $image_id
+                ",
+            )
+            .bind(("state_type", "Approved"))
+            .bind(("image_id", image_id))
+            .bind(("state", state))
+            .query("let $attempt = array::first($attempt)")
+            .query("let $later = select value ->previous->round from only $attempt")
+            .query(
+                r"
+                let $flagged = if array::len($later) > 0 {
+                    return update array::add($later, $attempt.out) set flagged = true;
+                } else {
+                    return [];
+                }
+                ",
+            )
+            .query("commit")
+            .query("$attempt")
+            .query("$flagged")
+            .await?
+            .better_check()?;
+        result
+            .take::<Option<Record<Attempt<TakenDown>>>>(4)?
+            .found()?;
+        let flagged = result.take::<Vec<Record<Round>>>(5)?;
+        Ok(flagged)
+    }
 
-        assert_eq!(expired.len(), 1);
+    /// Points an approved attempt at a replacement image.
+    pub async fn replace_approved_image(
+        &self,
+        admin: &Record<User>,
+        old_image_id: u64,
+        new_image_id: u64,
+    ) -> DbResult<RoundWithAttempts<Approved>> {
+        let audit = AuditEntry::new(
+            admin,
+            AuditAction::ReplaceImage {
+                old: old_image_id,
+                new: new_image_id,
+            },
+        );
+        let mut result = self
+            .db
+            .query("begin")
+            .query(
+                r"
+                let $attempt = update attempt
+                    set state.what = $new_image_id
+                    where state.type is $state_type
+                    and state.what is $old_image_id
+                ",
+            )
+            .bind(("state_type", "Approved"))
+            .bind(("old_image_id", old_image_id))
+            .bind(("new_image_id", new_image_id))
+            .query("let $attempt = array::first($attempt)")
+            .query("if $attempt is not none { create audit content $audit }")
+            .bind(("audit", audit))
+            .query("commit")
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .await?
+            .better_check()?;
+        let attempt = result
+            .take::<Option<RoundWithAttempts<Approved>>>(3)?
+            .found()?;
+        Ok(attempt)
     }
 
-    #[tokio::test]
-    async fn cancel_attempt() {
-        let (users, rounds, sut) = setup().await;
-        let user = users.create_or_update_user(0, "").await.unwrap();
-        rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
+    pub async fn force_expire_active_attempt(
+        &self,
+        admin: &Record<User>,
+        user: &Record<User>,
+        attempt: &Thing,
+    ) -> DbResult<Record<Attempt<Expired>>> {
+        let state = AttemptState::Expired {
+            inner: Expired { when: Utc::now() },
+        };
+        let action = AuditAction::ExpireAttempt {
+            user: user.id.clone(),
+        };
+        self.force_end_active_attempt(admin, user, attempt, state, action)
             .await
-            .unwrap();
-
-        sut.cancel_active_attempt(&user).await.unwrap();
     }
 
-    #[tokio::test]
-    async fn accept_unmoderated_attempt() {
-        let (users, rounds, sut) = setup().await;
-        let user = users.create_or_update_user(0, "").await.unwrap();
+    pub async fn force_cancel_active_attempt(
+        &self,
+        admin: &Record<User>,
+        user: &Record<User>,
+        attempt: &Thing,
+    ) -> DbResult<Record<Attempt<Cancelled>>> {
+        let state = AttemptState::Cancelled {
+            inner: Cancelled { when: Utc::now() },
+        };
+        let action = AuditAction::CancelAttempt {
+            user: user.id.clone(),
+        };
+        self.force_end_active_attempt(admin, user, attempt, state, action)
+            .await
+    }
+
+    async fn force_end_active_attempt<T: DeserializeOwned>(
+        &self,
+        admin: &Record<User>,
+        user: &Record<User>,
+        attempt: &Thing,
+        state: AttemptState,
+        action: AuditAction,
+    ) -> DbResult<Record<Attempt<T>>> {
+        let mut result = self
+            .db
+            .query("begin")
+            .query("let $attempt = update attempt set state = $state where id is $active and in is $user and state.type is $state_type")
+            .bind(("state_type", "Active"))
+            .bind(("active", attempt))
+            .bind(("user", &user.id))
+            .bind(("state", state))
+            .query("let $attempt = array::first($attempt)")
+            .query("if $attempt is not none { create audit content $audit }")
+            .bind(("audit", AuditEntry::new(admin, action)))
+            .query("commit")
+            .query("$attempt")
+            .await?
+            .better_check()?;
+        let attempt = result.take::<Option<Record<Attempt<T>>>>(3)?.found()?;
+        Ok(attempt)
+    }
+
+    /// Moves an active attempt to another user holding less than `max_active` unfinished attempts, keeping all of its progress.
+    /// The new holder must be free to join the round: a lobby member, not playing its mode already and allowed by `fairness`.
+    /// Party rounds are never reassigned.
+    pub async fn reassign_active_attempt(
+        &self,
+        admin: &Record<User>,
+        from: &Record<User>,
+        attempt: &Thing,
+        to: &Record<User>,
+        max_active: u64,
+        fairness: &Fairness,
+    ) -> DbResult<RoundWithAttempts<Active>> {
+        let audit = AuditEntry::new(
+            admin,
+            AuditAction::ReassignAttempt {
+                from: from.id.clone(),
+                to: to.id.clone(),
+            },
+        );
+        let mut result = self
+            .db
+            .query("begin")
+            .query("let $old = select * from attempt where id is $active and in is $from and state.type is $state_type")
+            .query("let $old = array::first($old)")
+            .query("let $busy = select value out.mode from attempt where in is $to and state.type in $unfinished_types")
+            .bind(("state_type", "Active"))
+            .bind(("unfinished_types", ["Active", "Paused"]))
+            .bind(("active", attempt))
+            .bind(("from", &from.id))
+            .bind(("to", &to.id))
+            .bind(("max_active", max_active))
+            .query(
+                r"
+                let $followed = if $no_self_follow {
+                    return array::flatten(select value ->previous->round from attempt where in is $to);
+                } else {
+                    return [];
+                }
+                ",
+            )
+            .bind(("no_self_follow", fairness.no_self_follow))
+            .query(
+                r"
+                let $free = if $old is not none and array::len($busy) < $max_active {
+                    return select value
+                        party is none
+                        and (lobby is none or lobby.members contains $to)
+                        and mode notinside $busy
+                        and id notinside $followed
+                        and ($max_contributions is none or array::len(<-previous<-(attempt where in is $to)) < $max_contributions)
+                        from only $old.out;
+                }
+                ",
+            )
+            .bind(("max_contributions", fairness.max_contributions))
+            .query(
+                r"
+                let $attempt = if $free {
+                    delete attempt where id is $old.id;
+                    create audit content $audit;
+                    let $round = $old.out;
+                    return relate only $to -> attempt -> $round content $old;
+                }
+                ",
+            )
+            .bind(("audit", audit))
+            .query("commit")
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .await?
+            .better_check()?;
+        let attempt = result
+            .take::<Option<RoundWithAttempts<Active>>>(6)?
+            .found()?;
+        Ok(attempt)
+    }
+
+    /// Review outcomes of the user's last `window` submissions outside of parties.
+    pub async fn get_review_record(
+        &self,
+        user: &Record<User>,
+        window: u32,
+    ) -> DbResult<ReviewRecord> {
+        let outcomes = self
+            .get_outcomes(user, window, &["Approved", "Rejected", "TakenDown"])
+            .await?;
+        let record = outcomes
+            .iter()
+            .fold(ReviewRecord::default(), |mut record, outcome| {
+                match outcome.state.as_str() {
+                    "Approved" => record.approved += 1,
+                    _ => record.rejected += 1,
+                }
+                record
+            });
+        Ok(record)
+    }
+
+    pub async fn get_reliability_record(
+        &self,
+        user: &Record<User>,
+        window: u32,
+    ) -> DbResult<ReliabilityRecord> {
+        let outcomes = self
+            .get_outcomes(user, window, &["Approved", "Expired", "Cancelled"])
+            .await?;
+        let record = outcomes
+            .iter()
+            .fold(ReliabilityRecord::default(), |mut record, outcome| {
+                match outcome.state.as_str() {
+                    "Approved" => {
+                        record.approved += 1;
+                        record.late += outcome.late.unwrap_or(false) as u64;
+                    }
+                    "Expired" => record.expired += 1,
+                    _ => record.cancelled += 1,
+                }
+                record
+            });
+        Ok(record)
+    }
+
+    /// The user's last `window` attempts outside of parties in one of `state_types`, rerolls aside.
+    async fn get_outcomes(
+        &self,
+        user: &Record<User>,
+        window: u32,
+        state_types: &[&str],
+    ) -> DbResult<Vec<Outcome>> {
+        let mut result = self
+            .db
+            .query(
+                r"
+                select state.type as state, late, created_at from attempt
+                    where in is $user
+                    and state.type in $state_types
+                    and out.party is none
+                    and rerolled is not true
+                    order by created_at desc
+                    limit $window
+                ",
+            )
+            .bind(("user", &user.id))
+            .bind(("state_types", state_types))
+            .bind(("window", window))
+            .await?
+            .better_check()?;
+        let outcomes = result.take::<Vec<Outcome>>(0)?;
+        Ok(outcomes)
+    }
+
+    /// Active attempts expiring within `in_advance` which were not reminded since that reminder became due.
+    /// Extending an attempt makes its reminders due again.
+    pub async fn get_due_for_reminder(
+        &self,
+        now: DateTime<Utc>,
+        in_advance: Duration,
+    ) -> DbResult<Vec<Record<Attempt<Active>>>> {
+        let mut result = self
+            .db
+            .query(
+                r"
+                select * from attempt
+                    where state.type is $state_type
+                    and state.until > $now
+                    and state.until <= $until
+                    and (reminded_at is none or <datetime> reminded_at + duration::from::secs($in_advance) < <datetime> state.until)
+                ",
+            )
+            .bind(("state_type", "Active"))
+            .bind(("now", now))
+            .bind(("until", now + in_advance))
+            .bind(("in_advance", in_advance.num_seconds()))
+            .await?
+            .better_check()?;
+        let attempts = result.take::<Vec<Record<Attempt<Active>>>>(0)?;
+        Ok(attempts)
+    }
+
+    pub async fn mark_reminded(&self, attempt: &Thing, when: DateTime<Utc>) -> DbResult<()> {
+        self.db
+            .query("update $attempt set reminded_at = $when")
+            .bind(("attempt", attempt))
+            .bind(("when", when))
+            .await?
+            .better_check()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::{AttemptRepository, ReliabilityRecord, ReviewRecord};
+    use crate::services::{
+        database::{
+            round::{Fairness, RoundRepository},
+            tests::db,
+            user::UserRepository,
+        },
+        gamemodes::Mode,
+        provider::Provider,
+    };
+
+    async fn setup() -> (UserRepository, RoundRepository, AttemptRepository) {
+        let db = db().await;
+        (db.get(), db.get(), db.get())
+    }
+
+    #[tokio::test]
+    async fn extend_attempt_to_avoid_expiration() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
+
+        let round = sut
+            .extend_active_attempt(&user, &active.attempt.id, Duration::seconds(60 * 60))
+            .await
+            .unwrap();
+        assert_eq!(round.attempt.extensions, 1);
+
+        let expired = sut.expire_active_attempts().await.unwrap();
+        assert!(expired.is_empty());
+    }
+
+    #[tokio::test]
+    async fn expire_one_out_of_two() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
         rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60 * 60))
             .await
             .unwrap();
 
-        sut.upload_active_attempt(&user).await.unwrap();
-        sut.approve_uploaded_attempt(&user, 0).await.unwrap();
+        let expired = sut.expire_active_attempts().await.unwrap();
+
+        assert_eq!(expired.len(), 1);
     }
 
     #[tokio::test]
-    async fn get_pending_attempt() {
+    async fn cancel_attempt() {
         let (users, rounds, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
+
+        sut.cancel_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn pause_and_resume_attempt() {
+        let (users, rounds, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let time_limit = Duration::seconds(60 * 60);
+        let active0 = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+
+        let paused = sut
+            .pause_active_attempt(&user0, &active0.attempt, Duration::seconds(60))
+            .await
+            .unwrap();
+
+        assert_eq!(paused.pauses, 1);
+        assert!(paused.state.remaining > 0);
+        assert!(sut.expire_active_attempts().await.unwrap().is_empty());
         rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
+            .attempt_existing_round(
+                &user1,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
+            .await
+            .unwrap_err();
+        let round = sut
+            .resume_paused_attempt(&user0, &paused.id, 1)
             .await
             .unwrap();
+        assert!(round.attempt.state.until > Utc::now());
+    }
 
-        sut.upload_active_attempt(&user).await.unwrap();
-        sut.moderate_uploaded_attempt(&user, 0).await.unwrap();
-        sut.get_pending_attempt(0).await.unwrap();
+    #[tokio::test]
+    async fn refuse_resuming_beside_attempt_of_same_mode() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let time_limit = Duration::seconds(60 * 60);
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+        let paused = sut
+            .pause_active_attempt(&user, &active.attempt, Duration::seconds(60))
+            .await
+            .unwrap();
+        let other = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+
+        sut.resume_paused_attempt(&user, &paused.id, 2)
+            .await
+            .unwrap_err();
+        sut.cancel_active_attempt(&user, &other.attempt.id)
+            .await
+            .unwrap();
+        sut.resume_paused_attempt(&user, &paused.id, 2)
+            .await
+            .unwrap();
     }
 
     #[tokio::test]
-    async fn accept_moderated_attempt() {
+    async fn expire_attempt_paused_for_too_long() {
         let (users, rounds, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        let paused = sut
+            .pause_active_attempt(&user, &active.attempt, Duration::seconds(-60))
+            .await
+            .unwrap();
+
+        let expired = sut.expire_active_attempts().await.unwrap();
 
+        assert_eq!(expired.len(), 1);
+        sut.resume_paused_attempt(&user, &paused.id, 1)
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn accept_unmoderated_attempt() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
+
+        sut.upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        sut.approve_uploaded_attempt(&user, &active.attempt.id, 0)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn release_active_attempt_without_skipping() {
+        let (users, rounds, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let time_limit = Duration::seconds(60);
         rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, Duration::seconds(-60))
+            .await
+            .unwrap();
+        sut.expire_active_attempts().await.unwrap();
+        let claimed = rounds
+            .attempt_existing_round(
+                &user1,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
+            .await
+            .unwrap();
+
+        sut.release_active_attempt(&user1, &claimed.attempt.id)
+            .await
+            .unwrap();
+
+        let again = rounds
+            .with_skips_since(Utc::now() - Duration::hours(1))
+            .attempt_existing_round(
+                &user1,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(again.round.id, claimed.round.id);
+    }
+
+    #[tokio::test]
+    async fn review_one_of_several_uploads() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let ross = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        let evolution = rounds
+            .attempt_new_round(&user, Mode::Evolution, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user, &ross.attempt.id)
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user, &evolution.attempt.id)
+            .await
+            .unwrap();
+
+        let approved = sut
+            .approve_uploaded_attempt(&user, &evolution.attempt.id, 0)
+            .await
+            .unwrap();
+        let pending = sut
+            .moderate_uploaded_attempt(&user, &ross.attempt.id, 1)
+            .await
+            .unwrap();
+
+        assert_eq!(approved.attempt.id, evolution.attempt.id);
+        assert_eq!(pending.id, ross.attempt.id);
+    }
+
+    #[tokio::test]
+    async fn upload_late_attempt_to_unclaimed_round() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let expired = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60))
+            .await
+            .unwrap();
+        sut.expire_active_attempts().await.unwrap();
+
+        let round = sut
+            .upload_late_attempt(
+                &user,
+                &expired.attempt.id,
+                Utc::now() - Duration::seconds(60),
+            )
+            .await
+            .unwrap();
+
+        assert!(round.attempt.late);
+        sut.approve_uploaded_attempt(&user, &round.attempt.id, 0)
             .await
             .unwrap();
-        sut.upload_active_attempt(&user).await.unwrap();
-        sut.moderate_uploaded_attempt(&user, 0).await.unwrap();
+        let record = sut.get_reliability_record(&user, 10).await.unwrap();
+        assert_eq!(record.approved, 1);
+        assert_eq!(record.late, 1);
+    }
 
+    #[tokio::test]
+    async fn upload_late_attempt_to_claimed_round() {
+        let (users, rounds, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let expired = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, Duration::seconds(-60))
+            .await
+            .unwrap();
+        sut.expire_active_attempts().await.unwrap();
         rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
+            .attempt_existing_round(
+                &user1,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                Duration::seconds(60),
+                &Fairness::default(),
+            )
             .await
             .unwrap();
-        sut.upload_active_attempt(&user).await.unwrap();
-        sut.moderate_uploaded_attempt(&user, 1).await.unwrap();
 
-        sut.approve_pending_attempt(&user, &user, 1, 2)
+        sut.upload_late_attempt(
+            &user0,
+            &expired.attempt.id,
+            Utc::now() - Duration::seconds(60),
+        )
+        .await
+        .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn upload_late_attempt_beside_active_attempt() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let expired = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60))
+            .await
+            .unwrap();
+        sut.expire_active_attempts().await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Evolution, 0, None, 1, Duration::seconds(60))
             .await
             .unwrap();
+        let since = Utc::now() - Duration::seconds(60);
+
+        let late = rounds.get_late_rounds(&user, since).await.unwrap();
+        sut.upload_late_attempt(&user, &active.attempt.id, since)
+            .await
+            .unwrap_err();
+        let round = sut
+            .upload_late_attempt(&user, &late[0].attempt.id, since)
+            .await
+            .unwrap();
+
+        assert_eq!(late.len(), 1);
+        assert_eq!(late[0].attempt.id, expired.attempt.id);
+        assert_eq!(round.attempt.id, expired.attempt.id);
+        let rounds = rounds.get_active_rounds(&user).await.unwrap();
+        assert_eq!(rounds.len(), 1);
+        assert_eq!(rounds[0].attempt.id, active.attempt.id);
     }
 
     #[tokio::test]
-    async fn reject_moderated_attempt() {
+    async fn get_pending_attempt() {
         let (users, rounds, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
+
+        sut.upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        sut.moderate_uploaded_attempt(&user, &active.attempt.id, 0)
+            .await
+            .unwrap();
+        sut.get_pending_attempt(0).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn take_down_reopens_round() {
+        let (users, rounds, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let time_limit = Duration::seconds(60);
+        let active0 = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user0, &active0.attempt.id)
+            .await
+            .unwrap();
+        sut.approve_uploaded_attempt(&user0, &active0.attempt.id, 0)
+            .await
+            .unwrap();
+
+        let flagged = sut.take_down_approved_attempt(&user1, 0).await.unwrap();
 
+        assert!(flagged.is_empty());
         rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
+            .attempt_existing_round(
+                &user1,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
             .await
             .unwrap();
-        sut.upload_active_attempt(&user).await.unwrap();
-        sut.moderate_uploaded_attempt(&user, 0).await.unwrap();
+    }
 
+    #[tokio::test]
+    async fn take_down_flags_advanced_chain() {
+        let (users, rounds, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let time_limit = Duration::seconds(60);
+        let active0 = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user0, &active0.attempt.id)
+            .await
+            .unwrap();
+        let round = sut
+            .approve_uploaded_attempt(&user0, &active0.attempt.id, 0)
+            .await
+            .unwrap();
         rounds
-            .attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
+            .forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
             .await
             .unwrap();
-        sut.upload_active_attempt(&user).await.unwrap();
-        sut.moderate_uploaded_attempt(&user, 1).await.unwrap();
 
-        sut.reject_pending_attempt(&user, &user, 1, 2)
+        let flagged = sut.take_down_approved_attempt(&user1, 0).await.unwrap();
+
+        assert_eq!(flagged.len(), 2);
+        assert!(flagged.iter().all(|r| r.flagged));
+        for round_no in 0..2 {
+            rounds
+                .attempt_existing_round(
+                    &user1,
+                    Mode::Ross,
+                    0,
+                    None,
+                    round_no,
+                    time_limit,
+                    &Fairness::default(),
+                )
+                .await
+                .unwrap_err();
+        }
+    }
+
+    #[tokio::test]
+    async fn restore_rejected_attempt_to_free_round() {
+        let (users, rounds, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let active0 = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user0, &active0.attempt.id)
+            .await
+            .unwrap();
+        sut.moderate_uploaded_attempt(&user0, &active0.attempt.id, 0)
+            .await
+            .unwrap();
+        sut.reject_pending_attempt(&user0, &user1, 0, 1)
+            .await
+            .unwrap();
+
+        let rejected = sut
+            .get_rejected_attempt_since(&user0, Utc::now() - Duration::seconds(60))
+            .await
+            .unwrap();
+        sut.get_rejected_attempt(&rejected.attempt.id)
+            .await
+            .unwrap();
+        let (round, conflicting) = sut
+            .restore_rejected_attempt(&user1, &rejected.attempt.id, 2)
             .await
             .unwrap();
+
+        assert!(!conflicting);
+        assert_eq!(round.attempt.state.what, 2);
+        sut.get_rejected_attempt_since(&user0, Utc::now() - Duration::seconds(60))
+            .await
+            .unwrap_err();
     }
 
     #[tokio::test]
-    async fn few_active_games_between() {
+    async fn restore_rejected_attempt_to_claimed_round() {
         let (users, rounds, sut) = setup().await;
-        let user1 = users.create_or_update_user(0, "").await.unwrap();
-        let user2 = users.create_or_update_user(0, "").await.unwrap();
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let time_limit = Duration::seconds(60);
+        let round = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user0, &round.attempt.id)
+            .await
+            .unwrap();
+        sut.moderate_uploaded_attempt(&user0, &round.attempt.id, 0)
+            .await
+            .unwrap();
+        sut.reject_pending_attempt(&user0, &user1, 0, 1)
+            .await
+            .unwrap();
         rounds
-            .attempt_new_round(&user1, Mode::Ross, false, 1, Duration::seconds(-60 * 60))
+            .attempt_existing_round(
+                &user1,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
+            .await
+            .unwrap();
+
+        let (restored, conflicting) = sut
+            .restore_rejected_attempt(&user1, &round.attempt.id, 2)
+            .await
+            .unwrap();
+
+        assert!(conflicting);
+        assert!(restored.round.conflicting);
+    }
+
+    #[tokio::test]
+    async fn replace_approved_image() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        sut.approve_uploaded_attempt(&user, &active.attempt.id, 0)
+            .await
+            .unwrap();
+
+        sut.replace_approved_image(&user, 0, 1).await.unwrap();
+
+        sut.get_approved_attempt(0).await.unwrap_err();
+        sut.get_approved_attempt(1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn force_expire_active_attempt() {
+        let (users, rounds, sut) = setup().await;
+        let admin = users.create_or_update_user(0, "").await.unwrap();
+        let user = users.create_or_update_user(1, "").await.unwrap();
+        let first = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        let second = rounds
+            .attempt_new_round(&user, Mode::Evolution, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+
+        sut.force_expire_active_attempt(&admin, &user, &first.attempt.id)
+            .await
+            .unwrap();
+
+        let active = rounds.get_active_rounds(&user).await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].attempt.id, second.attempt.id);
+        sut.force_cancel_active_attempt(&admin, &user, &first.attempt.id)
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn reassign_active_attempt() {
+        let (users, rounds, sut) = setup().await;
+        let admin = users.create_or_update_user(0, "").await.unwrap();
+        let user0 = users.create_or_update_user(1, "").await.unwrap();
+        let user1 = users.create_or_update_user(2, "").await.unwrap();
+        let round = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        let other = rounds
+            .attempt_new_round(&user0, Mode::Evolution, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+
+        let extended = sut
+            .extend_active_attempt(&user0, &round.attempt.id, Duration::seconds(120))
+            .await
+            .unwrap();
+
+        let reassigned = sut
+            .reassign_active_attempt(
+                &admin,
+                &user0,
+                &round.attempt.id,
+                &user1,
+                2,
+                &Fairness::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(reassigned.round.id, round.round.id);
+        assert_eq!(reassigned.attempt.who, user1.id);
+        assert_eq!(reassigned.attempt.state.until, extended.attempt.state.until);
+        assert_eq!(reassigned.attempt.extensions, 1);
+        let left = rounds.get_active_rounds(&user0).await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].attempt.id, other.attempt.id);
+        sut.reassign_active_attempt(
+            &admin,
+            &user0,
+            &other.attempt.id,
+            &user1,
+            1,
+            &Fairness::default(),
+        )
+        .await
+        .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn refuse_reassigning_to_player_of_mode() {
+        let (users, rounds, sut) = setup().await;
+        let admin = users.create_or_update_user(0, "").await.unwrap();
+        let user0 = users.create_or_update_user(1, "").await.unwrap();
+        let user1 = users.create_or_update_user(2, "").await.unwrap();
+        let round = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, Duration::seconds(60))
             .await
             .unwrap();
         rounds
-            .attempt_new_round(&user1, Mode::Ross, false, 1, Duration::seconds(-60))
+            .attempt_new_round(&user1, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+
+        sut.reassign_active_attempt(
+            &admin,
+            &user0,
+            &round.attempt.id,
+            &user1,
+            2,
+            &Fairness::default(),
+        )
+        .await
+        .unwrap_err();
+
+        let left = rounds.get_active_rounds(&user0).await.unwrap();
+        assert_eq!(left.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn review_record_of_recent_submissions() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let reviewer = users.create_or_update_user(1, "").await.unwrap();
+        for image in 0..3 {
+            let active = rounds
+                .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+                .await
+                .unwrap();
+            sut.upload_active_attempt(&user, &active.attempt.id)
+                .await
+                .unwrap();
+            sut.moderate_uploaded_attempt(&user, &active.attempt.id, image)
+                .await
+                .unwrap();
+            match image {
+                0 => sut
+                    .reject_pending_attempt(&user, &reviewer, image, 10 + image)
+                    .await
+                    .map(|_| ())
+                    .unwrap(),
+                _ => sut
+                    .approve_pending_attempt(&user, &reviewer, image, 10 + image)
+                    .await
+                    .map(|_| ())
+                    .unwrap(),
+            }
+        }
+
+        let all = sut.get_review_record(&user, 10).await.unwrap();
+        let recent = sut.get_review_record(&user, 2).await.unwrap();
+
+        assert_eq!(
+            all,
+            ReviewRecord {
+                approved: 2,
+                rejected: 1
+            }
+        );
+        assert_eq!(
+            recent,
+            ReviewRecord {
+                approved: 2,
+                rejected: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn reliability_record_of_recent_attempts() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let approved = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user, &approved.attempt.id)
+            .await
+            .unwrap();
+        sut.approve_uploaded_attempt(&user, &approved.attempt.id, 0)
+            .await
+            .unwrap();
+        let cancelled = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        sut.cancel_active_attempt(&user, &cancelled.attempt.id)
+            .await
+            .unwrap();
+        let rerolled = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        sut.reroll_active_attempt(&user, &rerolled.attempt.id)
             .await
             .unwrap();
         rounds
-            .attempt_new_round(&user2, Mode::Ross, false, 1, Duration::seconds(0))
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60))
             .await
             .unwrap();
-        let now = Utc::now();
+        sut.expire_active_attempts().await.unwrap();
+
+        let all = sut.get_reliability_record(&user, 10).await.unwrap();
+        let recent = sut.get_reliability_record(&user, 2).await.unwrap();
+
+        assert_eq!(
+            all,
+            ReliabilityRecord {
+                approved: 1,
+                late: 0,
+                expired: 1,
+                cancelled: 1
+            }
+        );
+        assert_eq!(recent.score(), 0.0);
+        assert_eq!(ReliabilityRecord::default().score(), 1.0);
+    }
 
-        let attempts = sut
-            .get_active_between(now - Duration::seconds(120), now + Duration::seconds(60))
+    #[tokio::test]
+    async fn accept_moderated_attempt() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        sut.moderate_uploaded_attempt(&user, &active.attempt.id, 0)
+            .await
+            .unwrap();
+
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        sut.moderate_uploaded_attempt(&user, &active.attempt.id, 1)
+            .await
+            .unwrap();
+
+        sut.approve_pending_attempt(&user, &user, 1, 2)
             .await
             .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reject_moderated_attempt() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
 
-        assert_eq!(attempts.len(), 2);
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        sut.moderate_uploaded_attempt(&user, &active.attempt.id, 0)
+            .await
+            .unwrap();
+
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(-60 * 60))
+            .await
+            .unwrap();
+        sut.upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        sut.moderate_uploaded_attempt(&user, &active.attempt.id, 1)
+            .await
+            .unwrap();
+
+        sut.reject_pending_attempt(&user, &user, 1, 2)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn remind_once_per_offset() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(20 * 60))
+            .await
+            .unwrap();
+        let now = Utc::now();
+        let (early, late) = (Duration::seconds(30 * 60), Duration::seconds(5 * 60));
+
+        assert!(sut
+            .get_due_for_reminder(now, late)
+            .await
+            .unwrap()
+            .is_empty());
+        let due = sut.get_due_for_reminder(now, early).await.unwrap();
+        assert_eq!(due.len(), 1);
+        sut.mark_reminded(&due[0].id, now).await.unwrap();
+        assert!(sut
+            .get_due_for_reminder(now, early)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let later = now + Duration::seconds(16 * 60);
+        let due = sut.get_due_for_reminder(later, late).await.unwrap();
+        assert_eq!(due.len(), 1);
+        sut.mark_reminded(&due[0].id, later).await.unwrap();
+        assert!(sut
+            .get_due_for_reminder(later, late)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(sut
+            .get_due_for_reminder(later, early)
+            .await
+            .unwrap()
+            .is_empty());
     }
 }
@@ -0,0 +1,110 @@
+use super::{user::User, BetterCheck, Database, DbResult, Record};
+use crate::services::provider::Provider;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AuditAction {
+    ReplaceImage { old: u64, new: u64 },
+    ExpireAttempt { user: Thing },
+    CancelAttempt { user: Thing },
+    ReassignAttempt { from: Thing, to: Thing },
+    DeleteChain { round: Thing },
+}
+
+/// Record of an admin action, written in the same transaction as the action itself.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub actor: Thing,
+    pub action: AuditAction,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(actor: &Record<User>, action: AuditAction) -> Self {
+        Self {
+            actor: actor.id.clone(),
+            action,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+pub struct AuditRepository {
+    db: Database,
+}
+
+impl<T> Provider<AuditRepository> for T
+where
+    T: Provider<Database>,
+{
+    fn get(&self) -> AuditRepository {
+        AuditRepository { db: self.get() }
+    }
+}
+
+impl AuditRepository {
+    pub async fn recent(&self, limit: u32) -> DbResult<Vec<Record<AuditEntry>>> {
+        let mut result = self
+            .db
+            .query("select * from audit order by created_at desc limit $limit")
+            .bind(("limit", limit))
+            .await?
+            .better_check()?;
+        let entries = result.take::<Vec<Record<AuditEntry>>>(0)?;
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuditAction, AuditRepository};
+    use crate::services::{
+        database::{
+            attempt::AttemptRepository, round::RoundRepository, tests::db, user::UserRepository,
+        },
+        gamemodes::Mode,
+        provider::Provider,
+    };
+    use chrono::Duration;
+
+    async fn setup() -> (
+        UserRepository,
+        RoundRepository,
+        AttemptRepository,
+        AuditRepository,
+    ) {
+        let db = db().await;
+        (db.get(), db.get(), db.get(), db.get())
+    }
+
+    #[tokio::test]
+    async fn audit_only_applied_actions() {
+        let (users, rounds, attempts, sut) = setup().await;
+        let admin = users.create_or_update_user(0, "").await.unwrap();
+        let user = users.create_or_update_user(1, "").await.unwrap();
+        let round = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        attempts
+            .force_cancel_active_attempt(&admin, &admin, &round.attempt.id)
+            .await
+            .unwrap_err();
+        attempts
+            .force_cancel_active_attempt(&admin, &user, &round.attempt.id)
+            .await
+            .unwrap();
+
+        let entries = sut.recent(10).await.unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor, admin.id);
+        assert!(matches!(
+            &entries[0].action,
+            AuditAction::CancelAttempt { user: target } if *target == user.id
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 use super::attempt::{Approved, Attempt};
 use crate::services::{
     database::{Database, DbResult},
+    gamemodes::{ross::Ross, GameLogic},
     provider::Provider,
 };
 
@@ -26,14 +27,16 @@ impl ByproductsRepository {
                 select * from attempt
                     where out.mode is $mode
                     and out.round_no in $round_nos
-                    and out.nsfw is false
+                    and out.rating is 0
                     and state.type = $state_type
+                    and skipped is not true
                     order by rand()
-                    limit 4
+                    limit $limit
                 ",
             )
             .bind(("mode", "Ross"))
-            .bind(("round_nos", &[0, 1, 2, 3]))
+            .bind(("round_nos", (0..Ross.last_round()).collect::<Vec<_>>()))
+            .bind(("limit", Ross.last_round()))
             .bind(("state_type", "Approved"))
             .await?;
         let attempts = result.take::<Vec<Attempt<Approved>>>(0)?;
@@ -48,7 +51,7 @@ mod tests {
         database::{
             attempt::AttemptRepository, round::RoundRepository, tests::db, user::UserRepository,
         },
-        gamemodes::Mode,
+        gamemodes::{ross::Ross, GameLogic, Mode},
         provider::Provider,
     };
     use chrono::Duration;
@@ -67,11 +70,14 @@ mod tests {
     async fn no_attributes_available() {
         let (users, rounds, attempts, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
-        rounds
-            .attempt_new_round(&user, Mode::Ross, false, 0, Duration::zero())
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 0, Duration::zero())
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user, &active.attempt.id)
             .await
             .unwrap();
-        attempts.upload_active_attempt(&user).await.unwrap();
 
         let attributes = sut.get_random_ross_attributes().await.unwrap();
 
@@ -82,21 +88,27 @@ mod tests {
     async fn enough_attributes_available() {
         let (users, rounds, attempts, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
-        for i in 0..4 {
-            rounds
-                .attempt_new_round(&user, Mode::Ross, false, 0, Duration::zero())
+        for i in 0..Ross.last_round() {
+            let active = rounds
+                .attempt_new_round(&user, Mode::Ross, 0, None, 0, Duration::zero())
+                .await
+                .unwrap();
+            attempts
+                .upload_active_attempt(&user, &active.attempt.id)
+                .await
+                .unwrap();
+            let round = attempts
+                .approve_uploaded_attempt(&user, &active.attempt.id, i)
                 .await
                 .unwrap();
-            attempts.upload_active_attempt(&user).await.unwrap();
-            let round = attempts.approve_uploaded_attempt(&user, i).await.unwrap();
             rounds
-                .forward_complete_round(&round.round, &round.attempt, round.round.forward())
+                .forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
                 .await
                 .unwrap();
         }
 
         let attributes = sut.get_random_ross_attributes().await.unwrap();
 
-        assert_eq!(attributes.len(), 4);
+        assert_eq!(attributes.len() as u64, Ross.last_round());
     }
 }
@@ -0,0 +1,210 @@
+use super::{user::User, BetterCheck, Database, DbResult, MapToNotFound, Record};
+use crate::services::provider::Provider;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lobby {
+    pub name: String,
+    pub owner: Thing,
+    pub members: Vec<Thing>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Lobby {
+    pub fn is_member(&self, user: &Record<User>) -> bool {
+        self.members.contains(&user.id)
+    }
+}
+
+pub struct LobbyRepository {
+    db: Database,
+}
+
+impl<T> Provider<LobbyRepository> for T
+where
+    T: Provider<Database>,
+{
+    fn get(&self) -> LobbyRepository {
+        LobbyRepository { db: self.get() }
+    }
+}
+
+impl LobbyRepository {
+    pub const TABLE: &str = "lobby";
+
+    pub async fn create(&self, name: &str, owner: &Record<User>) -> DbResult<Record<Lobby>> {
+        let lobby = Lobby {
+            name: name.to_owned(),
+            owner: owner.id.clone(),
+            members: vec![owner.id.clone()],
+            created_at: Utc::now(),
+        };
+        let lobby = self
+            .db
+            .create::<Option<Record<Lobby>>>((Self::TABLE, name))
+            .content(&lobby)
+            .await?
+            .found()?;
+        Ok(lobby)
+    }
+
+    pub async fn get(&self, name: &str) -> DbResult<Record<Lobby>> {
+        self.db
+            .select::<Option<Record<Lobby>>>((Self::TABLE, name))
+            .await?
+            .found()
+    }
+
+    pub async fn invite(
+        &self,
+        lobby: &Record<Lobby>,
+        user: &Record<User>,
+    ) -> DbResult<Record<Lobby>> {
+        let mut result = self
+            .db
+            .query("update only $lobby set members = array::union(members, [$user])")
+            .bind(("lobby", &lobby.id))
+            .bind(("user", &user.id))
+            .await?
+            .better_check()?;
+        let lobby = result.take::<Option<Record<Lobby>>>(0)?.found()?;
+        Ok(lobby)
+    }
+
+    /// Removes a member from the lobby, the owner cannot be removed.
+    pub async fn kick(
+        &self,
+        lobby: &Record<Lobby>,
+        user: &Record<User>,
+    ) -> DbResult<Record<Lobby>> {
+        let mut result = self
+            .db
+            .query("update $lobby set members -= $user where owner is not $user and members contains $user")
+            .bind(("lobby", &lobby.id))
+            .bind(("user", &user.id))
+            .await?
+            .better_check()?;
+        let lobby = result.take::<Vec<Record<Lobby>>>(0)?.pop().found()?;
+        Ok(lobby)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LobbyRepository;
+    use crate::services::{
+        database::{
+            round::{Fairness, RoundRepository},
+            tests::db,
+            user::UserRepository,
+            DbError,
+        },
+        gamemodes::Mode,
+        provider::Provider,
+    };
+    use chrono::Duration;
+
+    async fn setup() -> (UserRepository, RoundRepository, LobbyRepository) {
+        let db = db().await;
+        (db.get(), db.get(), db.get())
+    }
+
+    #[tokio::test]
+    async fn fail_second_create() {
+        let (users, _, sut) = setup().await;
+        let owner = users.create_or_update_user(0, "").await.unwrap();
+
+        sut.create("friends", &owner).await.unwrap();
+        sut.create("friends", &owner).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn invite_then_kick() {
+        let (users, _, sut) = setup().await;
+        let owner = users.create_or_update_user(0, "").await.unwrap();
+        let user = users.create_or_update_user(1, "").await.unwrap();
+        let lobby = sut.create("friends", &owner).await.unwrap();
+
+        let lobby = sut.invite(&lobby, &user).await.unwrap();
+        assert!(lobby.is_member(&user));
+        let lobby = sut.kick(&lobby, &user).await.unwrap();
+        assert!(!lobby.is_member(&user));
+    }
+
+    #[tokio::test]
+    async fn fail_kicking_owner() {
+        let (users, _, sut) = setup().await;
+        let owner = users.create_or_update_user(0, "").await.unwrap();
+        let lobby = sut.create("friends", &owner).await.unwrap();
+
+        let error = sut.kick(&lobby, &owner).await.unwrap_err();
+        assert!(matches!(error, DbError::NotFound));
+    }
+
+    #[tokio::test]
+    async fn members_attempt_lobby_rounds() {
+        let (users, rounds, sut) = setup().await;
+        let owner = users.create_or_update_user(0, "").await.unwrap();
+        let member = users.create_or_update_user(1, "").await.unwrap();
+        let lobby = sut.create("friends", &owner).await.unwrap();
+        sut.invite(&lobby, &member).await.unwrap();
+        let time_limit = Duration::seconds(0);
+
+        rounds
+            .attempt_new_round(&owner, Mode::Ross, 0, Some(&lobby.id), 2, time_limit)
+            .await
+            .unwrap();
+        rounds
+            .attempt_existing_round(
+                &member,
+                Mode::Ross,
+                0,
+                Some(&lobby.id),
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn fail_attempting_lobby_round_as_outsider() {
+        let (users, rounds, sut) = setup().await;
+        let owner = users.create_or_update_user(0, "").await.unwrap();
+        let outsider = users.create_or_update_user(1, "").await.unwrap();
+        let lobby = sut.create("friends", &owner).await.unwrap();
+        let time_limit = Duration::seconds(0);
+
+        rounds
+            .attempt_new_round(&owner, Mode::Ross, 0, Some(&lobby.id), 2, time_limit)
+            .await
+            .unwrap();
+        rounds
+            .attempt_existing_round(
+                &outsider,
+                Mode::Ross,
+                0,
+                Some(&lobby.id),
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
+            .await
+            .unwrap_err();
+        rounds
+            .attempt_existing_round(
+                &outsider,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
+            .await
+            .unwrap_err();
+    }
+}
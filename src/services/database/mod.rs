@@ -1,10 +1,18 @@
+pub mod appeal;
 pub mod assets;
 pub mod attempt;
+pub mod audit;
 pub mod byproducts;
+pub mod lobby;
 pub mod migrations;
+pub mod party;
+pub mod report;
 pub mod round;
 pub mod stats;
+pub mod subscription;
+pub mod theme;
 pub mod user;
+pub mod vote;
 
 use self::migrations::MigratorConfig;
 use serde::{Deserialize, Serialize};
@@ -155,6 +163,14 @@ mod tests {
             remove table round;
             remove table attempt;
             remove table previous;
+            remove table theme;
+            remove table vote;
+            remove table lobby;
+            remove table party;
+            remove table report;
+            remove table audit;
+            remove table appeal;
+            remove table subscription;
             ",
         )
         .await
@@ -0,0 +1,507 @@
+use super::{
+    attempt::{Active, Approved, AttemptState, CreateAttempt, Expired},
+    round::{Round, RoundStatus, RoundWithAttempts},
+    user::User,
+    BetterCheck, Database, DbResult, MapToNotFound, Record,
+};
+use crate::services::{
+    gamemodes::{GameLogic, Mode},
+    provider::Provider,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DurationSeconds};
+use surrealdb::sql::Thing;
+
+/// Synchronous game of a fixed group, with one chain per player.
+/// In round `r` player `i` plays chain `(i + r) % players`.
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Party {
+    pub mode: Mode,
+    pub players: Vec<Thing>,
+    pub chains: Vec<Thing>,
+    pub round_no: u64,
+    #[serde_as(as = "Option<DurationSeconds<i64>>")]
+    pub round_time: Option<Duration>,
+    pub deadline: DateTime<Utc>,
+    pub finished: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Party {
+    /// Shared time limit of a round, defaults to the mode's time limit.
+    pub fn time_limit(&self, round_no: u64) -> Duration {
+        self.round_time
+            .unwrap_or_else(|| self.mode.time_limit(round_no))
+    }
+
+    pub fn is_last_round(&self) -> bool {
+        self.round_no >= self.mode.last_round()
+    }
+
+    /// Player index playing the chain in the current round.
+    pub fn player_of(&self, chain: usize) -> usize {
+        let n = self.players.len();
+        (chain + n - self.round_no as usize % n) % n
+    }
+
+    /// Chain index played by the player in the current round.
+    pub fn chain_of(&self, player: usize) -> usize {
+        (player + self.round_no as usize) % self.players.len()
+    }
+}
+
+pub struct PartyRepository {
+    db: Database,
+}
+
+impl<T> Provider<PartyRepository> for T
+where
+    T: Provider<Database>,
+{
+    fn get(&self) -> PartyRepository {
+        PartyRepository { db: self.get() }
+    }
+}
+
+/// Party with the current round of every chain, assigned to the player whose turn it is, in order of the players.
+/// Finished parties have no rounds.
+#[derive(Debug)]
+pub struct AssignedParty {
+    pub party: Record<Party>,
+    pub rounds: Vec<RoundWithAttempts<Active>>,
+}
+
+/// Current turn of a chain once the party's deadline passed.
+#[derive(Debug)]
+pub enum Turn {
+    Played(RoundWithAttempts<Approved>),
+    /// Submitted, but still waiting for moderation.
+    InReview,
+    Missed(Record<Round>),
+}
+
+/// Approved stand-in for a missed turn, which has no image.
+#[derive(Debug, Serialize)]
+struct SkippedAttempt {
+    state: AttemptState,
+    created_at: DateTime<Utc>,
+    skipped: bool,
+}
+
+impl PartyRepository {
+    /// Creates the party together with one chain per player, in order of the players.
+    pub async fn create(
+        &self,
+        mode: Mode,
+        players: &[Record<User>],
+        round_time: Option<Duration>,
+    ) -> DbResult<AssignedParty> {
+        let now = Utc::now();
+        let party = Party {
+            mode,
+            players: players.iter().map(|p| p.id.clone()).collect(),
+            chains: vec![],
+            round_no: 0,
+            round_time,
+            deadline: now + round_time.unwrap_or_else(|| mode.time_limit(0)),
+            finished: false,
+            created_at: now,
+        };
+        let round = Round {
+            mode,
+            rating: 0,
+            round_no: 0,
+            multiplex: 1,
+            created_at: now,
+            theme: None,
+            status: RoundStatus::Open,
+            lobby: None,
+            party: None,
+            flagged: false,
+            conflicting: false,
+        };
+        let attempt = Self::active(party.deadline);
+        let mut query = self
+            .db
+            .query("begin")
+            .query("let $party = create only party content $party")
+            .bind(("party", party))
+            .bind(("round", round))
+            .bind(("attempt", attempt));
+        for (i, player) in players.iter().enumerate() {
+            query = query
+                .query(format!("let $round{i} = create only round content $round"))
+                .query(format!("let $round{i} = update only $round{i} set theme = fn::get_active_theme(), party = $party.id"))
+                .query(format!("let $attempt{i} = relate only $user{i} -> attempt -> $round{i} content $attempt"))
+                .bind((format!("user{i}"), &player.id));
+        }
+        let chains = (0..players.len())
+            .map(|i| format!("$round{i}.id"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        query = query
+            .query(format!(
+                "let $party = update only $party set chains = [{chains}]"
+            ))
+            .query("commit")
+            .query("return $party");
+        for i in 0..players.len() {
+            query = query.query(format!("fn::get_round_with_attempt($attempt{i})"));
+        }
+        let mut result = query.await?.better_check()?;
+        let first = 3 * players.len() + 2;
+        let party = result.take::<Option<Record<Party>>>(first)?.found()?;
+        let mut rounds = Vec::with_capacity(players.len());
+        for i in 0..players.len() {
+            let round = result
+                .take::<Option<RoundWithAttempts<Active>>>(first + 1 + i)?
+                .found()?;
+            rounds.push(round);
+        }
+        Ok(AssignedParty { party, rounds })
+    }
+
+    fn active(until: DateTime<Utc>) -> CreateAttempt {
+        CreateAttempt {
+            state: AttemptState::Active {
+                inner: Active { until },
+            },
+            created_at: Utc::now(),
+        }
+    }
+
+    pub async fn get_turn(&self, user: &Thing, round: &Thing) -> DbResult<Turn> {
+        let mut result = self
+            .db
+            .query("let $attempt = select * from only attempt where in is $user and out is $round and state.type is $state_type limit 1")
+            .bind(("user", user))
+            .bind(("round", round))
+            .bind(("state_type", "Approved"))
+            .query("fn::try_get_round_with_attempt($attempt)")
+            .query("select value id from attempt where in is $user and out is $round and state.type in $review_types")
+            .bind(("review_types", ["Uploading", "Pending"]))
+            .query("select * from only $round")
+            .await?;
+        if let Some(turn) = result.take::<Option<RoundWithAttempts<Approved>>>(1)? {
+            return Ok(Turn::Played(turn));
+        }
+        if !result.take::<Vec<Thing>>(2)?.is_empty() {
+            return Ok(Turn::InReview);
+        }
+        let round = result.take::<Option<Record<Round>>>(3)?.found()?;
+        Ok(Turn::Missed(round))
+    }
+
+    /// Forwards every chain with its approved attempt and moves the party to its next round, assigning each player their next chain.
+    /// Missed turns, given as `None`, are replaced with a skipped attempt and their unfinished attempts expire.
+    /// On the last round the party finishes instead, `next` holds the new round of each chain otherwise.
+    pub async fn advance(
+        &self,
+        party: &Record<Party>,
+        attempts: &[Option<Thing>],
+        next: &[Round],
+    ) -> DbResult<AssignedParty> {
+        let now = Utc::now();
+        let last = party.is_last_round();
+        let mut query = self
+            .db
+            .query("begin")
+            .bind(("party", &party.id))
+            .bind((
+                "expired",
+                AttemptState::Expired {
+                    inner: Expired { when: now },
+                },
+            ))
+            .bind(("unfinished_types", ["Active", "Paused"]));
+        let mut statements = 0;
+        for (i, (chain, attempt)) in party.chains.iter().zip(attempts).enumerate() {
+            let player = &party.players[party.player_of(i)];
+            query = query.bind((format!("chain{i}"), chain));
+            match attempt {
+                Some(attempt) => query = query.bind((format!("attempt{i}"), attempt)),
+                None => {
+                    let skipped = SkippedAttempt {
+                        state: AttemptState::Approved {
+                            inner: Approved {
+                                when: now,
+                                who: player.clone(),
+                                what: 0,
+                            },
+                        },
+                        created_at: now,
+                        skipped: true,
+                    };
+                    query = query
+                        .query(format!("update attempt set state = $expired where in is $player{i} and out is $chain{i} and state.type in $unfinished_types"))
+                        .query(format!("let $attempt{i} = (relate only $player{i} -> attempt -> $chain{i} content $skipped{i}).id"))
+                        .bind((format!("player{i}"), player))
+                        .bind((format!("skipped{i}"), skipped));
+                    statements += 2;
+                }
+            }
+            if !last {
+                query = query
+                    .query(format!(
+                        "let $round{i} = create only round content $next{i}"
+                    ))
+                    .query(format!(
+                        "relate ($chain{i}<-previous.in) -> previous -> $round{i}"
+                    ))
+                    .query(format!("relate only $attempt{i} -> previous -> $round{i}"))
+                    .bind((format!("next{i}"), &next[i]));
+                statements += 3;
+            }
+        }
+        let players = party.players.len();
+        query = match last {
+            true => query.query("let $party = update only $party set finished = true"),
+            false => {
+                let chains = (0..players)
+                    .map(|i| format!("$round{i}.id"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let deadline = now + party.time_limit(party.round_no + 1);
+                query = query
+                    .query(format!("let $party = update only $party set round_no += 1, chains = [{chains}], deadline = $deadline"))
+                    .bind(("deadline", deadline))
+                    .bind(("attempt", Self::active(deadline)));
+                // In the next round player `p` plays chain `(p + round_no + 1) % players`
+                for (p, player) in party.players.iter().enumerate() {
+                    let chain = (p + party.round_no as usize + 1) % players;
+                    query = query
+                        .query(format!("let $assigned{p} = relate only $assignee{p} -> attempt -> $round{chain} content $attempt"))
+                        .bind((format!("assignee{p}"), player));
+                }
+                query
+            }
+        };
+        let assigned = match last {
+            true => String::new(),
+            false => (0..players)
+                .map(|p| format!("$assigned{p}.id"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        let mut result = query
+            .query("commit")
+            .query("return $party")
+            .query(format!("return [{assigned}]"))
+            .await?
+            .better_check()?;
+        let first = statements + 1 + if last { 0 } else { players };
+        let party = result.take::<Option<Record<Party>>>(first)?.found()?;
+        let assigned = result.take::<Vec<Thing>>(first + 1)?;
+        // Resolving several rounds in one query overflows the stack of the embedded database
+        let mut rounds = Vec::with_capacity(assigned.len());
+        for attempt in assigned {
+            let mut result = self
+                .db
+                .query("let $attempt = select * from only $attempt")
+                .bind(("attempt", attempt))
+                .query("fn::get_round_with_attempt($attempt)")
+                .await?
+                .better_check()?;
+            let round = result
+                .take::<Option<RoundWithAttempts<Active>>>(1)?
+                .found()?;
+            rounds.push(round);
+        }
+        Ok(AssignedParty { party, rounds })
+    }
+
+    pub async fn get_due(&self, now: DateTime<Utc>) -> DbResult<Vec<Record<Party>>> {
+        let mut result = self
+            .db
+            .query("select * from party where finished is false and deadline < $now")
+            .bind(("now", now))
+            .await?
+            .better_check()?;
+        let parties = result.take::<Vec<Record<Party>>>(0)?;
+        Ok(parties)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PartyRepository, Turn};
+    use crate::services::{
+        database::{
+            attempt::AttemptRepository,
+            round::{Fairness, RoundRepository},
+            tests::db,
+            user::UserRepository,
+        },
+        gamemodes::{GameLogic, Mode},
+        provider::Provider,
+    };
+    use chrono::{Duration, Utc};
+
+    async fn setup() -> (
+        UserRepository,
+        RoundRepository,
+        AttemptRepository,
+        PartyRepository,
+    ) {
+        let db = db().await;
+        (db.get(), db.get(), db.get(), db.get())
+    }
+
+    #[tokio::test]
+    async fn rotate_chains_between_players() {
+        let (users, _, _, sut) = setup().await;
+        let players = [
+            users.create_or_update_user(0, "").await.unwrap(),
+            users.create_or_update_user(1, "").await.unwrap(),
+            users.create_or_update_user(2, "").await.unwrap(),
+        ];
+        let created = sut.create(Mode::Ross, &players, None).await.unwrap();
+        let mut party = created.party;
+
+        assert_eq!(party.chains.len(), 3);
+        assert_eq!(party.chains[1], created.rounds[1].round.id);
+        assert_eq!(created.rounds[1].attempt.who, players[1].id);
+        assert_eq!([0, 1, 2].map(|p| party.chain_of(p)), [0, 1, 2]);
+        party.round_no = 1;
+        assert_eq!([0, 1, 2].map(|p| party.chain_of(p)), [1, 2, 0]);
+        assert_eq!([0, 1, 2].map(|c| party.player_of(c)), [2, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn party_rounds_are_not_publicly_allocated() {
+        let (users, rounds, _, sut) = setup().await;
+        let players = [
+            users.create_or_update_user(0, "").await.unwrap(),
+            users.create_or_update_user(1, "").await.unwrap(),
+        ];
+        let outsider = users.create_or_update_user(2, "").await.unwrap();
+        let created = sut.create(Mode::Ross, &players, None).await.unwrap();
+
+        assert_eq!(
+            created.rounds[0].round.party.as_ref(),
+            Some(&created.party.id)
+        );
+        rounds
+            .attempt_existing_round(
+                &outsider,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                Duration::zero(),
+                &Fairness::default(),
+            )
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn wait_for_turns_in_review() {
+        let (users, _, attempts, sut) = setup().await;
+        let players = [
+            users.create_or_update_user(0, "").await.unwrap(),
+            users.create_or_update_user(1, "").await.unwrap(),
+        ];
+        let created = sut.create(Mode::Ross, &players, None).await.unwrap();
+        attempts
+            .upload_active_attempt(&players[0], &created.rounds[0].attempt.id)
+            .await
+            .unwrap();
+
+        let turn = sut
+            .get_turn(&players[0].id, &created.party.chains[0])
+            .await
+            .unwrap();
+
+        assert!(matches!(turn, Turn::InReview));
+    }
+
+    #[tokio::test]
+    async fn skip_missed_turn_and_assign_next_round() {
+        let (users, rounds, attempts, sut) = setup().await;
+        let players = [
+            users.create_or_update_user(0, "").await.unwrap(),
+            users.create_or_update_user(1, "").await.unwrap(),
+        ];
+        let created = sut
+            .create(Mode::Ross, &players, Some(Duration::hours(1)))
+            .await
+            .unwrap();
+        let party = created.party;
+        attempts
+            .upload_active_attempt(&players[0], &created.rounds[0].attempt.id)
+            .await
+            .unwrap();
+        attempts
+            .approve_uploaded_attempt(&players[0], &created.rounds[0].attempt.id, 0)
+            .await
+            .unwrap();
+
+        let Turn::Played(played) = sut
+            .get_turn(&players[0].id, &party.chains[0])
+            .await
+            .unwrap()
+        else {
+            panic!("Expected a played turn");
+        };
+        let Turn::Missed(missed) = sut
+            .get_turn(&players[1].id, &party.chains[1])
+            .await
+            .unwrap()
+        else {
+            panic!("Expected a missed turn");
+        };
+        let next = [played.round.forward(None), missed.forward(None)];
+        let advanced = sut
+            .advance(&party, &[Some(played.attempt.id.clone()), None], &next)
+            .await
+            .unwrap();
+        let assigned = &advanced.rounds[0];
+        let expired = rounds
+            .get_late_rounds(&players[1], Utc::now() - Duration::hours(1))
+            .await
+            .unwrap();
+
+        assert_eq!(advanced.party.round_no, 1);
+        assert_eq!(advanced.rounds.len(), 2);
+        assert_eq!(
+            assigned.round.id,
+            advanced.party.chains[advanced.party.chain_of(0)]
+        );
+        assert_eq!(assigned.round.round_no, 1);
+        assert_eq!(assigned.attempt.state.until, advanced.party.deadline);
+        assert_eq!(assigned.previous[0].who, players[1].id);
+        assert_eq!(assigned.previous[0].state.who, players[1].id);
+        assert!(assigned.previous[0].skipped);
+        assert_eq!(advanced.rounds[1].attempt.who, players[1].id);
+        assert_eq!(
+            advanced.rounds[1].round.id,
+            advanced.party.chains[advanced.party.chain_of(1)]
+        );
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].attempt.id, created.rounds[1].attempt.id);
+    }
+
+    #[tokio::test]
+    async fn finish_party_after_last_round() {
+        let (users, _, _, sut) = setup().await;
+        let players = [
+            users.create_or_update_user(0, "").await.unwrap(),
+            users.create_or_update_user(1, "").await.unwrap(),
+        ];
+        let mut party = sut
+            .create(Mode::Evolution, &players, None)
+            .await
+            .unwrap()
+            .party;
+        party.round_no = Mode::Evolution.last_round();
+
+        let advanced = sut.advance(&party, &[None, None], &[]).await.unwrap();
+
+        assert!(advanced.party.finished);
+        assert_eq!(advanced.party.round_no, 0);
+        assert!(advanced.rounds.is_empty());
+    }
+}
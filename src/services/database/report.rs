@@ -0,0 +1,170 @@
+use super::{user::User, BetterCheck, Database, DbResult, MapToNotFound, Record};
+use crate::services::provider::Provider;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(tag = "type")]
+pub enum ReportStatus {
+    #[default]
+    Open,
+    Upheld {
+        who: Thing,
+        when: DateTime<Utc>,
+    },
+    Dismissed {
+        who: Thing,
+        when: DateTime<Utc>,
+    },
+}
+
+/// Report of a published image, identified by its message in the moderation queue.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Report {
+    pub attempt: Thing,
+    pub reporter: Thing,
+    pub channel: u64,
+    pub image: u64,
+    pub status: ReportStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct ReportRepository {
+    db: Database,
+}
+
+impl<T> Provider<ReportRepository> for T
+where
+    T: Provider<Database>,
+{
+    fn get(&self) -> ReportRepository {
+        ReportRepository { db: self.get() }
+    }
+}
+
+impl ReportRepository {
+    pub const TABLE: &str = "report";
+
+    pub async fn create(
+        &self,
+        queued: u64,
+        reporter: &Record<User>,
+        attempt: &Thing,
+        channel: u64,
+        image: u64,
+    ) -> DbResult<Record<Report>> {
+        let report = Report {
+            attempt: attempt.clone(),
+            reporter: reporter.id.clone(),
+            channel,
+            image,
+            status: ReportStatus::Open,
+            created_at: Utc::now(),
+        };
+        let report = self
+            .db
+            .create::<Option<Record<Report>>>((Self::TABLE, queued))
+            .content(&report)
+            .await?
+            .found()?;
+        Ok(report)
+    }
+
+    pub async fn is_reported(&self, attempt: &Thing) -> DbResult<bool> {
+        let mut result = self
+            .db
+            .query("select value id from report where attempt is $attempt and status.type is $status_type")
+            .bind(("attempt", attempt))
+            .bind(("status_type", "Open"))
+            .await?
+            .better_check()?;
+        let reports = result.take::<Vec<Thing>>(0)?;
+        Ok(!reports.is_empty())
+    }
+
+    pub async fn get_open(&self, queued: u64) -> DbResult<Record<Report>> {
+        self.db
+            .select::<Option<Record<Report>>>((Self::TABLE, queued))
+            .await?
+            .filter(|r| matches!(r.status, ReportStatus::Open))
+            .found()
+    }
+
+    pub async fn resolve(
+        &self,
+        report: &Record<Report>,
+        moderator: &Record<User>,
+        upheld: bool,
+    ) -> DbResult<Record<Report>> {
+        let (who, when) = (moderator.id.clone(), Utc::now());
+        let status = match upheld {
+            true => ReportStatus::Upheld { who, when },
+            false => ReportStatus::Dismissed { who, when },
+        };
+        let mut result = self
+            .db
+            .query("update only $report set status = $status")
+            .bind(("report", &report.id))
+            .bind(("status", status))
+            .await?
+            .better_check()?;
+        let report = result.take::<Option<Record<Report>>>(0)?.found()?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReportRepository;
+    use crate::services::{
+        database::{
+            attempt::AttemptRepository, round::RoundRepository, tests::db, user::UserRepository,
+            DbError,
+        },
+        gamemodes::Mode,
+        provider::Provider,
+    };
+    use chrono::Duration;
+
+    async fn setup() -> (
+        UserRepository,
+        RoundRepository,
+        AttemptRepository,
+        ReportRepository,
+    ) {
+        let db = db().await;
+        (db.get(), db.get(), db.get(), db.get())
+    }
+
+    #[tokio::test]
+    async fn report_then_dismiss() {
+        let (users, rounds, attempts, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let active0 = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user0, &active0.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user0, &active0.attempt.id, 7)
+            .await
+            .unwrap();
+
+        let report = sut
+            .create(1, &user1, &round.attempt.id, 2, 7)
+            .await
+            .unwrap();
+        assert!(sut.is_reported(&round.attempt.id).await.unwrap());
+        sut.get_open(1).await.unwrap();
+
+        sut.resolve(&report, &user0, false).await.unwrap();
+        assert!(!sut.is_reported(&round.attempt.id).await.unwrap());
+        let error = sut.get_open(1).await.unwrap_err();
+        assert!(matches!(error, DbError::NotFound));
+    }
+}
@@ -1,5 +1,6 @@
 use super::{
-    attempt::{Active, Approved, Attempt, AttemptState, CreateAttempt},
+    attempt::{Active, Approved, Attempt, AttemptState, CreateAttempt, Expired, Paused},
+    audit::{AuditAction, AuditEntry},
     user::User,
     Record,
 };
@@ -11,26 +12,57 @@ use crate::services::{
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::ops::Deref;
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(tag = "type")]
+pub enum RoundStatus {
+    #[default]
+    Open,
+    Voting {
+        until: DateTime<Utc>,
+    },
+    Decided {
+        winner: Thing,
+    },
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Round {
     pub mode: Mode,
-    pub nsfw: bool,
+    #[serde(default)]
+    pub rating: u64,
     pub round_no: u64,
     pub multiplex: u64,
     pub created_at: DateTime<Utc>,
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub status: RoundStatus,
+    pub lobby: Option<Thing>,
+    pub party: Option<Thing>,
+    #[serde(default)]
+    pub flagged: bool,
+    #[serde(default)]
+    pub conflicting: bool,
 }
 
 impl Round {
-    pub fn forward(&self) -> Self {
+    /// The next round of the chain, `multiplex` overrides the mode's own for competitive rounds.
+    pub fn forward(&self, multiplex: Option<u64>) -> Self {
         let round_no = self.round_no + 1;
         let created_at = Utc::now();
         Self {
             mode: self.mode,
-            nsfw: self.nsfw,
-            round_no: self.round_no + 1,
-            multiplex: self.mode.multiplex(round_no),
+            rating: self.rating,
+            round_no,
+            multiplex: multiplex.unwrap_or_else(|| self.mode.multiplex(round_no)),
             created_at,
+            theme: self.theme.clone(),
+            status: RoundStatus::Open,
+            lobby: self.lobby.clone(),
+            party: self.party.clone(),
+            flagged: self.flagged,
+            conflicting: false,
         }
     }
 }
@@ -56,8 +88,38 @@ pub struct RoundWithPreviousAttempts {
     pub previous: Vec<Record<Attempt<Approved>>>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ClosedVoting {
+    pub winner: RoundWithAttempts<Approved>,
+    pub next: RoundWithPreviousAttempts,
+}
+
+/// Who may be allocated an existing round of a chain, enforced while joining rounds.
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+pub struct Fairness {
+    /// Refuse rounds directly following one of the user's own attempts.
+    #[serde(default)]
+    pub no_self_follow: bool,
+    /// Maximum attempts per user in a chain, 1 means never participating twice.
+    pub max_contributions: Option<u64>,
+}
+
+/// Which of the eligible rounds is joined, rounds with the least previous participation are always preferred.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy)]
+pub enum AllocationStrategy {
+    #[default]
+    Random,
+    OldestFirst,
+    /// Rounds with the oldest latest attempt, or creation if never attempted.
+    LeastRecentlyTouched,
+    /// Random, with chance proportional to the age of the round.
+    WeightedByAge,
+}
+
 pub struct RoundRepository {
     db: Database,
+    allocation: AllocationStrategy,
+    skipped_since: Option<DateTime<Utc>>,
 }
 
 impl<T> Provider<RoundRepository> for T
@@ -65,16 +127,32 @@ where
     T: Provider<Database>,
 {
     fn get(&self) -> RoundRepository {
-        RoundRepository { db: self.get() }
+        RoundRepository {
+            db: self.get(),
+            allocation: AllocationStrategy::default(),
+            skipped_since: None,
+        }
     }
 }
 
 impl RoundRepository {
+    pub fn with_allocation(mut self, allocation: AllocationStrategy) -> Self {
+        self.allocation = allocation;
+        self
+    }
+
+    /// Excludes rounds the user cancelled or let expire since `since`, along with the rest of their chains.
+    pub fn with_skips_since(mut self, since: DateTime<Utc>) -> Self {
+        self.skipped_since = Some(since);
+        self
+    }
+
     pub async fn attempt_new_round(
         &self,
         user: &Record<User>,
         mode: Mode,
-        nsfw: bool,
+        rating: u64,
+        lobby: Option<&Thing>,
         multiplex: u64,
         time_limit: Duration,
     ) -> DbResult<RoundWithAttempts<Active>> {
@@ -82,10 +160,16 @@ impl RoundRepository {
         let now = Utc::now();
         let round = Round {
             mode,
-            nsfw,
+            rating,
             round_no,
             multiplex,
             created_at: now,
+            theme: None,
+            status: RoundStatus::Open,
+            lobby: lobby.cloned(),
+            party: None,
+            flagged: false,
+            conflicting: false,
         };
         let attempt = CreateAttempt {
             state: AttemptState::Active {
@@ -98,8 +182,9 @@ impl RoundRepository {
         let mut result = self
             .db
             .query("begin")
-            .query("let $round = create round content $round")
+            .query("let $round = create only round content $round")
             .bind(("round", round))
+            .query("let $round = update only $round set theme = fn::get_active_theme()")
             .query("let $attempt = relate only $user -> attempt -> $round content $attempt")
             .bind(("user", user))
             .bind(("attempt", attempt))
@@ -108,7 +193,7 @@ impl RoundRepository {
             .await?
             .better_check()?;
         let round = result
-            .take::<Option<RoundWithAttempts<Active>>>(2)?
+            .take::<Option<RoundWithAttempts<Active>>>(3)?
             .found()?;
         Ok(round)
     }
@@ -138,13 +223,121 @@ impl RoundRepository {
         Ok(round)
     }
 
+    /// Opens voting on a competitive round once all of its drafts were approved.
+    pub async fn try_open_voting(
+        &self,
+        round: &Record<Round>,
+        until: DateTime<Utc>,
+    ) -> DbResult<Option<Record<Round>>> {
+        let status = RoundStatus::Voting { until };
+        let mut result = self
+            .db
+            .query(
+                r"
+                update $round
+                    set status = $status
+                    where status.type is $status_type
+                    and array::len(<-(attempt where state.type is $state_type)) >= multiplex
+                ",
+            )
+            .bind(("round", &round.id))
+            .bind(("status", status))
+            .bind(("status_type", "Open"))
+            .bind(("state_type", "Approved"))
+            .await?
+            .better_check()?;
+        let round = result.take::<Vec<Record<Round>>>(0)?.pop();
+        Ok(round)
+    }
+
+    pub async fn get_expired_votings(&self, now: DateTime<Utc>) -> DbResult<Vec<Record<Round>>> {
+        let mut result = self
+            .db
+            .query("select * from round where status.type is $status_type and status.until < $now")
+            .bind(("status_type", "Voting"))
+            .bind(("now", now))
+            .await?
+            .better_check()?;
+        let rounds = result.take::<Vec<Record<Round>>>(0)?;
+        Ok(rounds)
+    }
+
+    /// Decides a competitive round in favour of its most voted draft, earlier approvals win ties, and forwards the winner to `next`.
+    /// Remaining drafts stay approved as alternates and are never forwarded.
+    /// Rounds that lost all their drafts while voting are reopened for new drafts instead.
+    pub async fn close_voting(
+        &self,
+        round: &Record<Round>,
+        next: Round,
+    ) -> DbResult<Option<ClosedVoting>> {
+        let mut result = self
+            .db
+            .query("begin")
+            .query(
+                r"
+                let $winner = (
+                    select *, array::len(<-vote) as votes
+                        from attempt
+                        where out is $round
+                        and state.type is $state_type
+                        order by votes desc, state.when
+                        limit 1
+                )[0]
+                ",
+            )
+            .bind(("round", &round.id))
+            .bind(("state_type", "Approved"))
+            .query(
+                r"
+                let $closed = if $winner is none {
+                    update $round set status = { type: $open } where status.type is $status_type;
+                    return [];
+                } else {
+                    return update $round
+                        set status = { type: $decided, winner: $winner.id }
+                        where status.type is $status_type;
+                }
+                ",
+            )
+            .bind(("open", "Open"))
+            .bind(("decided", "Decided"))
+            .bind(("status_type", "Voting"))
+            .query(
+                r"
+                let $next = if array::any($closed) {
+                    let $next = create only round content $next;
+                    relate ($round<-previous.in) -> previous -> $next;
+                    relate only ($winner.id) -> previous -> $next;
+                    return $next;
+                }
+                ",
+            )
+            .bind(("next", next))
+            .query("commit")
+            .query(
+                r"
+                if $next is not none {
+                    return { winner: fn::get_round_with_attempt($winner), next: fn::get_round($next) };
+                }
+                ",
+            )
+            .await?
+            .better_check()?;
+        let closed = result.take::<Option<ClosedVoting>>(3)?;
+        Ok(closed)
+    }
+
+    /// Joins a round rated at most `max_rating` that the fairness policy allows the user into, picked by the allocation strategy.
+    #[allow(clippy::too_many_arguments)]
     pub async fn attempt_existing_round(
         &self,
         user: &Record<User>,
         mode: Mode,
-        nsfw: bool,
+        max_rating: u64,
+        lobby: Option<&Thing>,
         round_no: u64,
         time_limit: Duration,
+        fairness: &Fairness,
     ) -> DbResult<RoundWithAttempts<Active>> {
         let now = Utc::now();
         let attempt = CreateAttempt {
@@ -160,83 +353,298 @@ impl RoundRepository {
             .query("begin")
             .query(
                 r"
-                let $random = select
+                let $skipped = if $skipped_since is none {
+                    return [];
+                } else {
+                    return select value out from attempt
+                        where in is $user
+                        and state.type in $skipped_state_types
+                        and <datetime> state.when >= <datetime> $skipped_since;
+                }
+                ",
+            )
+            .bind(("skipped_since", self.skipped_since))
+            .bind(("skipped_state_types", ["Cancelled", "Expired"]))
+            .query(
+                r"
+                let $followed = if $no_self_follow {
+                    return array::flatten(select value ->previous->round from attempt
+                        where in is $user
+                        and out.round_no is $previous_round_no);
+                } else {
+                    return [];
+                }
+                ",
+            )
+            .bind(("no_self_follow", fairness.no_self_follow))
+            .bind(("previous_round_no", round_no.checked_sub(1)))
+            .query(
+                r"
+                let $eligible = select
                     *,
-                    array::len(<-previous<-(attempt where in is $user)) as previously_participated
+                    array::len(<-previous<-(attempt where in is $user)) as previously_participated,
+                    <datetime> created_at as opened_at,
+                    array::max(array::concat([<datetime> created_at], <array<datetime>> <-attempt.created_at)) as touched_at,
+                    (if duration::secs(time::now() - <datetime> created_at) > 0
+                        then math::pow(rand::float(), 1f / duration::secs(time::now() - <datetime> created_at))
+                        else 0 end) as age_weighted
                     from round
                     where mode = $mode
-                    and nsfw = $nsfw
+                    and rating <= $max_rating
                     and round_no = $round_no
+                    and lobby is $lobby
+                    and party is none
+                    and flagged is not true
+                    and status.type is $status_type
+                    and ($lobby is none or $lobby.members contains $user)
                     and array::len(<-(attempt where state.type in $allocating_state_types)) < multiplex
                     and array::any(<-(attempt where state.type in $allocating_state_types and $user is in)) is false
+                    and id notinside $followed
+                    and ($max_contributions is none or array::len(<-previous<-(attempt where in is $user)) < $max_contributions)
+                    and id notinside $skipped
+                    and array::len(array::intersect(<-previous<-attempt.out, $skipped)) = 0
                     order by rand()
                 ",
             )
+            .bind(("max_contributions", fairness.max_contributions))
             .bind(("user", &user.id))
             .bind(("mode", mode))
-            .bind(("nsfw", nsfw))
+            .bind(("max_rating", max_rating))
             .bind(("round_no", round_no))
+            .bind(("lobby", lobby))
+            .bind(("status_type", "Open"))
             .query(
                 r"
-                let $attempt_result = if array::any($random) {
-                    let $round = select * from only $random order by previously_participated limit 1;
+                let $attempt_result = if array::any($eligible) {
+                    let $round = if $allocation is 'OldestFirst' {
+                        return select * from only $eligible order by previously_participated, opened_at limit 1
+                    } else if $allocation is 'LeastRecentlyTouched' {
+                        return select * from only $eligible order by previously_participated, touched_at limit 1
+                    } else if $allocation is 'WeightedByAge' {
+                        return select * from only $eligible order by previously_participated, age_weighted desc limit 1
+                    } else {
+                        return select * from only $eligible order by previously_participated limit 1
+                    };
                     return relate only $user -> attempt -> $round content $attempt;
                 }
                 "
             )
+            .bind(("allocation", self.allocation))
             .bind(("attempt", attempt))
             .query("commit")
             .query("fn::try_get_round_with_attempt($attempt_result)")
             .await?
         .better_check()?;
         let round = result
-            .take::<Option<RoundWithAttempts<Active>>>(2)?
+            .take::<Option<RoundWithAttempts<Active>>>(4)?
             .found()?;
         Ok(round)
     }
 
-    pub async fn get_active_round(
+    /// Re-rates a round, rounds forwarded from it inherit the new rating.
+    pub async fn set_rating(&self, round: &Thing, rating: u64) -> DbResult<Record<Round>> {
+        let mut result = self
+            .db
+            .query("update only $round set rating = $rating")
+            .bind(("round", round))
+            .bind(("rating", rating))
+            .await?
+            .better_check()?;
+        let round = result.take::<Option<Record<Round>>>(0)?.found()?;
+        Ok(round)
+    }
+
+    /// Deletes every round of the chain containing `round` along with its attempts, votes and previous edges.
+    pub async fn delete_chain(
+        &self,
+        admin: &Record<User>,
+        round: &Thing,
+    ) -> DbResult<Vec<Record<Round>>> {
+        let audit = AuditEntry::new(
+            admin,
+            AuditAction::DeleteChain {
+                round: round.clone(),
+            },
+        );
+        let mut result = self
+            .db
+            .query("begin")
+            .query("let $earlier = select value out from attempt where ->previous->round contains $round")
+            .bind(("round", round))
+            .query("let $root = select value id from round where id in array::add($earlier, $round) and round_no = 0")
+            .query("let $root = array::first($root)")
+            .query("let $later = select value ->previous->round from attempt where out is $root")
+            .query("let $chain = array::distinct(array::add(array::flatten($later), $root))")
+            .query(
+                r"
+                let $deleted = if $root is not none {
+                    delete vote where round in $chain;
+                    delete previous where out in $chain;
+                    delete attempt where out in $chain;
+                    create audit content $audit;
+                    return delete round where id in $chain return before;
+                }
+                ",
+            )
+            .bind(("audit", audit))
+            .query("commit")
+            .query("$deleted")
+            .await?
+            .better_check()?;
+        let deleted = result.take::<Vec<Record<Round>>>(6)?;
+        Some(deleted).filter(|d| !d.is_empty()).found()
+    }
+
+    /// Reveals the partial images of the complete game ending in `round` once `at` passes.
+    pub async fn schedule_reveal(&self, round: &Thing, at: DateTime<Utc>) -> DbResult<()> {
+        self.db
+            .query("update $round set reveal_at = $at")
+            .bind(("round", round))
+            .bind(("at", at))
+            .await?
+            .better_check()?;
+        Ok(())
+    }
+
+    pub async fn get_due_reveals(&self, now: DateTime<Utc>) -> DbResult<Vec<Record<Round>>> {
+        let mut result = self
+            .db
+            .query("select * from round where reveal_at is not none and <datetime> reveal_at <= <datetime> $now")
+            .bind(("now", now))
+            .await?
+            .better_check()?;
+        let rounds = result.take::<Vec<Record<Round>>>(0)?;
+        Ok(rounds)
+    }
+
+    /// Every approved image of the earlier rounds of the chain, including drafts that lost a vote.
+    pub async fn get_partial_images(&self, round: &Thing) -> DbResult<Vec<u64>> {
+        let mut result = self
+            .db
+            .query("let $chain = $round<-previous<-attempt.out")
+            .bind(("round", round))
+            .query(
+                r"
+                select value state.what from attempt
+                    where state.type is $state_type
+                    and out inside $chain
+                    and skipped is not true
+                ",
+            )
+            .bind(("state_type", "Approved"))
+            .await?
+            .better_check()?;
+        let images = result.take::<Vec<u64>>(1)?;
+        Ok(images)
+    }
+
+    pub async fn finish_reveal(&self, round: &Thing) -> DbResult<()> {
+        self.db
+            .query("update $round set reveal_at = none")
+            .bind(("round", round))
+            .await?
+            .better_check()?;
+        Ok(())
+    }
+
+    /// Every active attempt of the user with its round, oldest first.
+    pub async fn get_active_rounds(
         &self,
         user: &Record<User>,
-    ) -> DbResult<RoundWithAttempts<Active>> {
+    ) -> DbResult<Vec<RoundWithAttempts<Active>>> {
         let mut result = self
             .db
-            .query("let $attempt = select * from only attempt where in is $user and state.type is $state_type")
+            .query("let $attempts = select * from attempt where in is $user and state.type is $state_type order by created_at")
             .bind(("user", &user.id))
             .bind(("state_type", "Active"))
-            .query("fn::try_get_round_with_attempt($attempt)")
-            .await?;
-        let round = result
-            .take::<Option<RoundWithAttempts<Active>>>(1)?
-            .found()?;
-        Ok(round)
+            .query("select value fn::get_round_with_attempt($this) from $attempts")
+            .await?
+            .better_check()?;
+        let rounds = result.take::<Vec<RoundWithAttempts<Active>>>(1)?;
+        Ok(rounds)
+    }
+
+    pub async fn get_paused_rounds(
+        &self,
+        user: &Record<User>,
+    ) -> DbResult<Vec<RoundWithAttempts<Paused>>> {
+        let mut result = self
+            .db
+            .query("let $attempts = select * from attempt where in is $user and state.type is $state_type order by created_at")
+            .bind(("user", &user.id))
+            .bind(("state_type", "Paused"))
+            .query("select value fn::get_round_with_attempt($this) from $attempts")
+            .await?
+            .better_check()?;
+        let rounds = result.take::<Vec<RoundWithAttempts<Paused>>>(1)?;
+        Ok(rounds)
+    }
+
+    /// Rounds of the user's attempts which expired after `since`, which may still be submitted late.
+    pub async fn get_late_rounds(
+        &self,
+        user: &Record<User>,
+        since: DateTime<Utc>,
+    ) -> DbResult<Vec<RoundWithAttempts<Expired>>> {
+        let mut result = self
+            .db
+            .query("let $attempts = select * from attempt where in is $user and state.type is $state_type and state.when > $since order by created_at")
+            .bind(("user", &user.id))
+            .bind(("state_type", "Expired"))
+            .bind(("since", since))
+            .query("select value fn::get_round_with_attempt($this) from $attempts")
+            .await?
+            .better_check()?;
+        let rounds = result.take::<Vec<RoundWithAttempts<Expired>>>(1)?;
+        Ok(rounds)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::RoundRepository;
+    use super::{AllocationStrategy, Fairness, RoundRepository};
     use crate::services::{
-        database::{attempt::AttemptRepository, tests::db, user::UserRepository, DbError},
+        database::{
+            attempt::AttemptRepository, tests::db, user::UserRepository, BetterCheck, DbError,
+        },
         gamemodes::Mode,
         provider::Provider,
     };
-    use chrono::Duration;
+    use chrono::{Duration, Utc};
+    use surrealdb::sql::Thing;
 
     async fn setup() -> (UserRepository, AttemptRepository, RoundRepository) {
         let db = db().await;
         (db.get(), db.get(), db.get())
     }
 
+    async fn backdate(sut: &RoundRepository, record: &Thing, age: Duration) {
+        sut.db
+            .query("update $record set created_at = $created_at")
+            .bind(("record", record))
+            .bind(("created_at", Utc::now() - age))
+            .await
+            .unwrap()
+            .better_check()
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn create_then_find_round() {
         let (users, _, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
 
-        sut.attempt_new_round(&user, Mode::Ross, false, 1, Duration::seconds(0))
+        sut.attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(0))
             .await
             .unwrap();
-        sut.get_active_round(&user).await.unwrap();
+        sut.attempt_new_round(&user, Mode::Evolution, 0, None, 1, Duration::seconds(0))
+            .await
+            .unwrap();
+
+        let rounds = sut.get_active_rounds(&user).await.unwrap();
+
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].round.mode, Mode::Ross);
     }
 
     #[tokio::test]
@@ -244,16 +652,28 @@ mod tests {
         let (users, attempts, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
         let mode = Mode::Ross;
-        let nsfw = false;
+        let rating = 0;
         let time_limit = Duration::seconds(0);
 
-        sut.attempt_new_round(&user, mode, nsfw, 1, time_limit)
+        let active = sut
+            .attempt_new_round(&user, mode, rating, None, 1, time_limit)
             .await
             .unwrap();
-        attempts.cancel_active_attempt(&user).await.unwrap();
-        sut.attempt_existing_round(&user, mode, nsfw, 0, time_limit)
+        attempts
+            .cancel_active_attempt(&user, &active.attempt.id)
             .await
             .unwrap();
+        sut.attempt_existing_round(
+            &user,
+            mode,
+            rating,
+            None,
+            0,
+            time_limit,
+            &Fairness::default(),
+        )
+        .await
+        .unwrap();
     }
 
     #[tokio::test]
@@ -261,14 +681,22 @@ mod tests {
         let (users, _, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
         let mode = Mode::Ross;
-        let nsfw = false;
+        let rating = 0;
         let time_limit = Duration::seconds(0);
 
-        sut.attempt_new_round(&user, mode, nsfw, 1, time_limit)
+        sut.attempt_new_round(&user, mode, rating, None, 1, time_limit)
             .await
             .unwrap();
         let error = sut
-            .attempt_existing_round(&user, mode, nsfw, 0, time_limit)
+            .attempt_existing_round(
+                &user,
+                mode,
+                rating,
+                None,
+                0,
+                time_limit,
+                &Fairness::default(),
+            )
             .await
             .unwrap_err();
         assert!(matches!(error, DbError::NotFound))
@@ -280,15 +708,23 @@ mod tests {
         let user0 = users.create_or_update_user(0, "").await.unwrap();
         let user1 = users.create_or_update_user(1, "").await.unwrap();
         let mode = Mode::Ross;
-        let nsfw = false;
+        let rating = 0;
         let time_limit = Duration::seconds(0);
 
-        sut.attempt_new_round(&user0, mode, nsfw, 1, time_limit)
+        sut.attempt_new_round(&user0, mode, rating, None, 1, time_limit)
             .await
             .unwrap();
-        sut.attempt_existing_round(&user1, mode, nsfw, 0, time_limit)
-            .await
-            .unwrap_err();
+        sut.attempt_existing_round(
+            &user1,
+            mode,
+            rating,
+            None,
+            0,
+            time_limit,
+            &Fairness::default(),
+        )
+        .await
+        .unwrap_err();
     }
 
     #[tokio::test]
@@ -297,15 +733,23 @@ mod tests {
         let user0 = users.create_or_update_user(0, "").await.unwrap();
         let user1 = users.create_or_update_user(1, "").await.unwrap();
         let mode = Mode::Ross;
-        let nsfw = false;
+        let rating = 0;
         let time_limit = Duration::seconds(0);
 
-        sut.attempt_new_round(&user0, mode, nsfw, 2, time_limit)
-            .await
-            .unwrap();
-        sut.attempt_existing_round(&user1, mode, nsfw, 0, time_limit)
+        sut.attempt_new_round(&user0, mode, rating, None, 2, time_limit)
             .await
             .unwrap();
+        sut.attempt_existing_round(
+            &user1,
+            mode,
+            rating,
+            None,
+            0,
+            time_limit,
+            &Fairness::default(),
+        )
+        .await
+        .unwrap();
     }
 
     #[tokio::test]
@@ -313,17 +757,99 @@ mod tests {
         let (users, attempts, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
         let mode = Mode::Ross;
-        let nsfw = false;
+        let rating = 0;
         let time_limit = Duration::seconds(0);
-        sut.attempt_new_round(&user, mode, nsfw, 2, time_limit)
+        let active = sut
+            .attempt_new_round(&user, mode, rating, None, 2, time_limit)
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user, &active.attempt.id, 0)
+            .await
+            .unwrap();
+
+        sut.forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn attempt_rounds_up_to_max_rating() {
+        let (users, _, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+
+        sut.attempt_new_round(&user0, mode, 2, None, 2, time_limit)
+            .await
+            .unwrap();
+        let error = sut
+            .attempt_existing_round(&user1, mode, 1, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap_err();
+        sut.attempt_existing_round(&user1, mode, 3, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+
+        assert!(matches!(error, DbError::NotFound))
+    }
+
+    #[tokio::test]
+    async fn rerate_round() {
+        let (users, _, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        let round = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
             .await
             .unwrap();
-        attempts.upload_active_attempt(&user).await.unwrap();
-        let round = attempts.approve_uploaded_attempt(&user, 0).await.unwrap();
 
-        sut.forward_complete_round(&round.round, &round.attempt, round.round.forward())
+        let rerated = sut.set_rating(&round.round.id, 2).await.unwrap();
+        let error = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap_err();
+
+        assert_eq!(rerated.rating, 2);
+        assert_eq!(rerated.forward(None).rating, 2);
+        assert!(matches!(error, DbError::NotFound))
+    }
+
+    #[tokio::test]
+    async fn delete_chain() {
+        let (users, attempts, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let time_limit = Duration::seconds(60);
+        let active = sut
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user, &active.attempt.id, 0)
             .await
             .unwrap();
+        let next = sut
+            .forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
+            .await
+            .unwrap();
+
+        let deleted = sut.delete_chain(&user, &next.round.id).await.unwrap();
+
+        assert_eq!(deleted.len(), 2);
+        attempts.get_approved_attempt(0).await.unwrap_err();
+        let error = sut.delete_chain(&user, &next.round.id).await.unwrap_err();
+        assert!(matches!(error, DbError::NotFound))
     }
 
     #[tracing_test::traced_test]
@@ -333,22 +859,384 @@ mod tests {
         let (users, attempts, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
         let mode = Mode::Ross;
-        let nsfw = false;
+        let rating = 0;
         let time_limit = Duration::seconds(0);
-        sut.attempt_new_round(&user, mode, nsfw, 2, time_limit)
+        let active = sut
+            .attempt_new_round(&user, mode, rating, None, 2, time_limit)
             .await
             .unwrap();
-        attempts.upload_active_attempt(&user).await.unwrap();
-        let round = attempts.approve_uploaded_attempt(&user, 0).await.unwrap();
-        sut.forward_complete_round(&round.round, &round.attempt, round.round.forward())
+        attempts
+            .upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user, &active.attempt.id, 0)
+            .await
+            .unwrap();
+        sut.forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
             .await
             .unwrap();
 
         let result = sut
-            .attempt_existing_round(&user, mode, nsfw, 1, time_limit)
+            .attempt_existing_round(
+                &user,
+                mode,
+                rating,
+                None,
+                1,
+                time_limit,
+                &Fairness::default(),
+            )
             .await
             .unwrap();
 
         assert_eq!(result.previous.len(), 1);
     }
+
+    #[tokio::test]
+    async fn refuse_following_own_attempt() {
+        let (users, attempts, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        let active = sut
+            .attempt_new_round(&user0, mode, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user0, &active.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user0, &active.attempt.id, 0)
+            .await
+            .unwrap();
+        sut.forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
+            .await
+            .unwrap();
+        let fairness = Fairness {
+            no_self_follow: true,
+            max_contributions: None,
+        };
+
+        let error = sut
+            .attempt_existing_round(&user0, mode, 0, None, 1, time_limit, &fairness)
+            .await
+            .unwrap_err();
+        let result = sut
+            .attempt_existing_round(&user0, mode, 0, None, 1, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+
+        assert!(matches!(error, DbError::NotFound));
+        assert_eq!(result.previous.len(), 1);
+        assert_eq!(result.previous[0].who, user0.id);
+    }
+
+    #[tokio::test]
+    async fn limit_contributions_per_chain() {
+        let (users, attempts, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        let active = sut
+            .attempt_new_round(&user0, mode, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user0, &active.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user0, &active.attempt.id, 0)
+            .await
+            .unwrap();
+        sut.forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
+            .await
+            .unwrap();
+        let active = sut
+            .attempt_existing_round(&user1, mode, 0, None, 1, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user1, &active.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user1, &active.attempt.id, 1)
+            .await
+            .unwrap();
+        sut.forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
+            .await
+            .unwrap();
+        let once = Fairness {
+            no_self_follow: true,
+            max_contributions: Some(1),
+        };
+        let twice = Fairness {
+            no_self_follow: true,
+            max_contributions: Some(2),
+        };
+
+        let error = sut
+            .attempt_existing_round(&user0, mode, 0, None, 2, time_limit, &once)
+            .await
+            .unwrap_err();
+        let result = sut
+            .attempt_existing_round(&user0, mode, 0, None, 2, time_limit, &twice)
+            .await
+            .unwrap();
+
+        assert!(matches!(error, DbError::NotFound));
+        assert_eq!(result.previous.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn allocate_randomly_among_eligible_rounds() {
+        let (users, _, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        sut.attempt_new_round(&user0, mode, 0, None, 1, time_limit)
+            .await
+            .unwrap();
+        let open = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        let sut = sut.with_allocation(AllocationStrategy::Random);
+
+        let result = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.round.id, open.round.id);
+    }
+
+    #[tokio::test]
+    async fn allocate_oldest_round_first() {
+        let (users, _, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        let newer = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        let older = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        backdate(&sut, &newer.round.id, Duration::hours(1)).await;
+        backdate(&sut, &older.round.id, Duration::hours(2)).await;
+        let sut = sut.with_allocation(AllocationStrategy::OldestFirst);
+
+        let result = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.round.id, older.round.id);
+    }
+
+    #[tokio::test]
+    async fn allocate_least_recently_touched_round_first() {
+        let (users, _, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        let touched = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        let untouched = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        backdate(&sut, &touched.round.id, Duration::hours(3)).await;
+        backdate(&sut, &untouched.round.id, Duration::hours(2)).await;
+        backdate(&sut, &untouched.attempt.id, Duration::hours(2)).await;
+        let sut = sut.with_allocation(AllocationStrategy::LeastRecentlyTouched);
+
+        let result = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.round.id, untouched.round.id);
+    }
+
+    #[tokio::test]
+    async fn never_allocate_brand_new_round_weighted_by_age() {
+        let (users, _, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        let aged = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        sut.attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        backdate(&sut, &aged.round.id, Duration::hours(1)).await;
+        let sut = sut.with_allocation(AllocationStrategy::WeightedByAge);
+
+        let result = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.round.id, aged.round.id);
+    }
+
+    #[tokio::test]
+    async fn skip_cancelled_round() {
+        let (users, attempts, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        sut.attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        let active = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+        let cancelled = attempts
+            .cancel_active_attempt(&user1, &active.attempt.id)
+            .await
+            .unwrap();
+        let sut = sut.with_skips_since(cancelled.state.when - Duration::hours(1));
+
+        let error = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap_err();
+        let other = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        let result = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+
+        assert!(matches!(error, DbError::NotFound));
+        assert_eq!(result.round.id, other.round.id);
+    }
+
+    #[tokio::test]
+    async fn offer_round_skipped_before_window() {
+        let (users, attempts, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        let round = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        let active = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+        let cancelled = attempts
+            .cancel_active_attempt(&user1, &active.attempt.id)
+            .await
+            .unwrap();
+        let sut = sut.with_skips_since(cancelled.state.when + Duration::seconds(1));
+
+        let result = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.round.id, round.round.id);
+    }
+
+    #[tokio::test]
+    async fn skip_rest_of_skipped_chain() {
+        let (users, attempts, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let mode = Mode::Ross;
+        let time_limit = Duration::seconds(0);
+        let active0 = sut
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        let active1 = sut
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+        let cancelled = attempts
+            .cancel_active_attempt(&user1, &active1.attempt.id)
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user0, &active0.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user0, &active0.attempt.id, 0)
+            .await
+            .unwrap();
+        sut.forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
+            .await
+            .unwrap();
+        let sut = sut.with_skips_since(cancelled.state.when - Duration::hours(1));
+
+        let error = sut
+            .attempt_existing_round(&user1, mode, 0, None, 1, time_limit, &Fairness::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, DbError::NotFound));
+    }
+
+    #[tokio::test]
+    async fn reveal_partial_images_once_due() {
+        let (users, attempts, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let active = sut
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::seconds(60))
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user, &active.attempt.id, 1)
+            .await
+            .unwrap();
+        let next = sut
+            .forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
+            .await
+            .unwrap();
+        let now = Utc::now();
+        sut.schedule_reveal(&next.round.id, now).await.unwrap();
+
+        let early = sut
+            .get_due_reveals(now - Duration::seconds(1))
+            .await
+            .unwrap();
+        let due = sut.get_due_reveals(now).await.unwrap();
+        let images = sut.get_partial_images(&next.round.id).await.unwrap();
+        sut.finish_reveal(&next.round.id).await.unwrap();
+        let revealed = sut.get_due_reveals(now).await.unwrap();
+
+        assert!(early.is_empty());
+        assert_eq!(due.len(), 1);
+        assert_eq!(images, [1]);
+        assert!(revealed.is_empty());
+    }
 }
@@ -1,9 +1,15 @@
 use serde::Deserialize;
+use surrealdb::sql::Thing;
 
-use super::{round::Round, user::User, Record};
+use super::{
+    attempt::Approved,
+    round::{Round, RoundWithAttempts},
+    user::User,
+    Record,
+};
 use crate::services::{
     database::{BetterCheck, Database, DbResult},
-    gamemodes::Mode,
+    gamemodes::{GameLogic, Mode},
     provider::Provider,
 };
 
@@ -29,7 +35,8 @@ pub struct ActiveUser {
 #[derive(Debug, Deserialize)]
 pub struct UnallocatedRound {
     pub mode: Mode,
-    pub nsfw: bool,
+    pub rating: u64,
+    pub theme: Option<String>,
     pub round_no: u64,
     pub unallocated: u64,
 }
@@ -55,26 +62,70 @@ impl StatsRepository {
         Ok(users)
     }
 
-    pub async fn get_unallocated_rounds(&self) -> DbResult<Vec<UnallocatedRound>> {
+    /// Unallocated rounds of every theme, or of a single theme when one is provided.
+    /// Public rounds are listed without a lobby, otherwise only rounds of that lobby.
+    pub async fn get_unallocated_rounds(
+        &self,
+        theme: Option<&str>,
+        lobby: Option<&Thing>,
+    ) -> DbResult<Vec<UnallocatedRound>> {
         let mut result = self
             .db
             .query(
                 r"
                 select
-                    nsfw,
+                    rating,
                     mode,
+                    theme,
                     round_no,
                     math::sum(multiplex - array::len(<-(attempt where state.type in $allocating_state_types))) as unallocated
                     from round
-                    group by nsfw, mode, round_no
-                    order by nsfw, mode, round_no
+                    where ($theme is none or theme is $theme)
+                    and lobby is $lobby
+                    and party is none
+                    and flagged is not true
+                    group by rating, mode, theme, round_no
+                    order by rating, mode, theme, round_no
                 ",
             )
+            .bind(("theme", theme))
+            .bind(("lobby", lobby))
             .await?
             .better_check()?;
         let rounds = result.take::<Vec<UnallocatedRound>>(0)?;
         Ok(rounds)
     }
+
+    /// Approved final rounds with the attempts of their chains, of every theme or of a single one, oldest first per mode.
+    pub async fn get_complete_chains(
+        &self,
+        theme: Option<&str>,
+    ) -> DbResult<Vec<RoundWithAttempts<Approved>>> {
+        let mut chains = Vec::new();
+        for mode in Mode::ALL {
+            let mut result = self
+                .db
+                .query(
+                    r"
+                    let $finals = select * from attempt
+                        where state.type is $approved_state
+                        and out.mode is $mode
+                        and out.round_no is $round_no
+                        and ($theme is none or out.theme is $theme)
+                        order by created_at
+                    ",
+                )
+                .query("select value fn::get_round_with_attempt($this) from $finals")
+                .bind(("approved_state", "Approved"))
+                .bind(("mode", mode))
+                .bind(("round_no", mode.last_round()))
+                .bind(("theme", theme))
+                .await?
+                .better_check()?;
+            chains.extend(result.take::<Vec<RoundWithAttempts<Approved>>>(1)?);
+        }
+        Ok(chains)
+    }
 }
 
 #[cfg(test)]
@@ -82,52 +133,84 @@ mod tests {
     use super::StatsRepository;
     use crate::services::{
         database::{
-            attempt::AttemptRepository, round::RoundRepository, tests::db, user::UserRepository,
+            attempt::AttemptRepository,
+            lobby::LobbyRepository,
+            round::RoundRepository,
+            tests::db,
+            theme::{Theme, ThemeRepository},
+            user::UserRepository,
         },
-        gamemodes::Mode,
+        gamemodes::{GameLogic, Mode},
         provider::Provider,
     };
-    use chrono::Duration;
+    use chrono::{Duration, Utc};
+    use surrealdb::sql::Thing;
 
     async fn setup() -> (
         UserRepository,
         RoundRepository,
         AttemptRepository,
+        ThemeRepository,
+        LobbyRepository,
         StatsRepository,
     ) {
         let db = db().await;
-        (db.get(), db.get(), db.get(), db.get())
+        (db.get(), db.get(), db.get(), db.get(), db.get(), db.get())
+    }
+
+    async fn make_final(sut: &StatsRepository, round: &Thing) {
+        sut.db
+            .query("update $round set round_no = $round_no")
+            .bind(("round", round))
+            .bind(("round_no", Mode::Evolution.last_round()))
+            .await
+            .unwrap();
     }
 
     #[tokio::test]
     async fn list_active_users() {
-        let (users, rounds, attempts, sut) = setup().await;
+        let (users, rounds, attempts, _, _, sut) = setup().await;
         let user0 = users.create_or_update_user(0, "").await.unwrap();
         let user1 = users.create_or_update_user(1, "").await.unwrap();
         let user2 = users.create_or_update_user(2, "").await.unwrap();
         let user3 = users.create_or_update_user(3, "").await.unwrap();
         let _ = users.create_or_update_user(4, "").await.unwrap();
-        rounds
-            .attempt_new_round(&user0, Mode::Ross, false, 1, Duration::zero())
+        let active0 = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 1, Duration::zero())
             .await
             .unwrap();
-        rounds
-            .attempt_new_round(&user1, Mode::Ross, false, 1, Duration::zero())
+        let active1 = rounds
+            .attempt_new_round(&user1, Mode::Ross, 0, None, 1, Duration::zero())
             .await
             .unwrap();
-        rounds
-            .attempt_new_round(&user2, Mode::Ross, false, 1, Duration::zero())
+        let active2 = rounds
+            .attempt_new_round(&user2, Mode::Ross, 0, None, 1, Duration::zero())
             .await
             .unwrap();
         rounds
-            .attempt_new_round(&user3, Mode::Ross, false, 1, Duration::zero())
+            .attempt_new_round(&user3, Mode::Ross, 0, None, 1, Duration::zero())
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user0, &active0.attempt.id)
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user1, &active1.attempt.id)
+            .await
+            .unwrap();
+        attempts
+            .moderate_uploaded_attempt(&user1, &active1.attempt.id, 1)
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user2, &active2.attempt.id)
+            .await
+            .unwrap();
+        attempts
+            .approve_uploaded_attempt(&user2, &active2.attempt.id, 2)
             .await
             .unwrap();
-        attempts.upload_active_attempt(&user0).await.unwrap();
-        attempts.upload_active_attempt(&user1).await.unwrap();
-        attempts.moderate_uploaded_attempt(&user1, 1).await.unwrap();
-        attempts.upload_active_attempt(&user2).await.unwrap();
-        attempts.approve_uploaded_attempt(&user2, 2).await.unwrap();
 
         let active_users = sut.get_active_users().await.unwrap();
 
@@ -136,25 +219,34 @@ mod tests {
 
     #[tokio::test]
     async fn list_unallocated_rounds() {
-        let (users, rounds, attempts, sut) = setup().await;
+        let (users, rounds, attempts, _, _, sut) = setup().await;
         let user = users.create_or_update_user(0, "").await.unwrap();
-        rounds
-            .attempt_new_round(&user, Mode::Ross, true, 4, Duration::zero())
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 2, None, 4, Duration::zero())
             .await
             .unwrap();
-        attempts.cancel_active_attempt(&user).await.unwrap();
-        rounds
-            .attempt_new_round(&user, Mode::Ross, false, 2, Duration::zero())
+        attempts
+            .cancel_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 2, Duration::zero())
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user, &active.attempt.id, 0)
             .await
             .unwrap();
-        attempts.upload_active_attempt(&user).await.unwrap();
-        let round = attempts.approve_uploaded_attempt(&user, 0).await.unwrap();
         rounds
-            .forward_complete_round(&round.round, &round.attempt, round.round.forward())
+            .forward_complete_round(&round.round, &round.attempt, round.round.forward(None))
             .await
             .unwrap();
 
-        let unallocated_rounds = sut.get_unallocated_rounds().await.unwrap();
+        let unallocated_rounds = sut.get_unallocated_rounds(None, None).await.unwrap();
 
         assert_eq!(
             unallocated_rounds
@@ -164,4 +256,125 @@ mod tests {
             &[1, 2, 4]
         );
     }
+
+    #[tokio::test]
+    async fn list_unallocated_rounds_of_theme() {
+        let (users, rounds, attempts, themes, _, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 2, Duration::zero())
+            .await
+            .unwrap();
+        attempts
+            .cancel_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        let now = Utc::now();
+        themes
+            .create(Theme {
+                name: "Space".to_owned(),
+                starts_at: now - Duration::hours(1),
+                ends_at: now + Duration::hours(1),
+            })
+            .await
+            .unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 4, Duration::zero())
+            .await
+            .unwrap();
+        attempts
+            .cancel_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+
+        let all = sut.get_unallocated_rounds(None, None).await.unwrap();
+        let themed = sut
+            .get_unallocated_rounds(Some("Space"), None)
+            .await
+            .unwrap();
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(themed.len(), 1);
+        assert_eq!(themed[0].unallocated, 4);
+    }
+
+    #[tokio::test]
+    async fn list_complete_chains_of_theme() {
+        let (users, rounds, attempts, themes, _, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let unthemed = rounds
+            .attempt_new_round(&user, Mode::Evolution, 0, None, 1, Duration::zero())
+            .await
+            .unwrap();
+        let now = Utc::now();
+        themes
+            .create(Theme {
+                name: "Space".to_owned(),
+                starts_at: now - Duration::hours(1),
+                ends_at: now + Duration::hours(1),
+            })
+            .await
+            .unwrap();
+        let themed = rounds
+            .attempt_new_round(&user, Mode::Evolution, 0, None, 1, Duration::zero())
+            .await
+            .unwrap();
+        let unfinished = rounds
+            .attempt_new_round(&user, Mode::Evolution, 0, None, 1, Duration::zero())
+            .await
+            .unwrap();
+        make_final(&sut, &unthemed.round.id).await;
+        make_final(&sut, &themed.round.id).await;
+        for (round, what) in [(&unthemed, 1), (&themed, 2), (&unfinished, 3)] {
+            attempts
+                .upload_active_attempt(&user, &round.attempt.id)
+                .await
+                .unwrap();
+            attempts
+                .approve_uploaded_attempt(&user, &round.attempt.id, what)
+                .await
+                .unwrap();
+        }
+
+        let all = sut.get_complete_chains(None).await.unwrap();
+        let space = sut.get_complete_chains(Some("Space")).await.unwrap();
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(space.len(), 1);
+        assert_eq!(space[0].attempt.state.what, 2);
+    }
+
+    #[tokio::test]
+    async fn list_unallocated_rounds_of_lobby() {
+        let (users, rounds, attempts, _, lobbies, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let lobby = lobbies.create("friends", &user).await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 2, Duration::zero())
+            .await
+            .unwrap();
+        attempts
+            .cancel_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, Some(&lobby.id), 4, Duration::zero())
+            .await
+            .unwrap();
+        attempts
+            .cancel_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+
+        let public = sut.get_unallocated_rounds(None, None).await.unwrap();
+        let private = sut
+            .get_unallocated_rounds(None, Some(&lobby.id))
+            .await
+            .unwrap();
+
+        assert_eq!(public.len(), 1);
+        assert_eq!(public[0].unallocated, 2);
+        assert_eq!(private.len(), 1);
+        assert_eq!(private[0].unallocated, 4);
+    }
 }
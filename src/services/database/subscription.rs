@@ -0,0 +1,186 @@
+use super::{round::Round, user::User, BetterCheck, Database, DbResult, MapToNotFound, Record};
+use crate::services::{gamemodes::Mode, provider::Provider};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+/// Request to hear about the next joinable round of a mode, used up by the first match.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Subscription {
+    pub user: Thing,
+    pub mode: Mode,
+    /// Any round of the mode matches when missing.
+    pub round_no: Option<u64>,
+    pub max_rating: u64,
+    /// Join the round on behalf of the user instead of only telling them about it.
+    pub auto_claim: bool,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct SubscriptionRepository {
+    db: Database,
+}
+
+impl<T> Provider<SubscriptionRepository> for T
+where
+    T: Provider<Database>,
+{
+    fn get(&self) -> SubscriptionRepository {
+        SubscriptionRepository { db: self.get() }
+    }
+}
+
+impl SubscriptionRepository {
+    /// Replaces any subscription of the user to the same mode.
+    pub async fn subscribe(
+        &self,
+        user: &Record<User>,
+        mode: Mode,
+        round_no: Option<u64>,
+        max_rating: u64,
+        auto_claim: bool,
+        ttl: Duration,
+    ) -> DbResult<Record<Subscription>> {
+        let now = Utc::now();
+        let subscription = Subscription {
+            user: user.id.clone(),
+            mode,
+            round_no,
+            max_rating,
+            auto_claim,
+            expires_at: now + ttl,
+            created_at: now,
+        };
+        let mut result = self
+            .db
+            .query("begin")
+            .query("delete subscription where user is $user and mode = $mode")
+            .query("create only subscription content $subscription")
+            .query("commit")
+            .bind(("user", &user.id))
+            .bind(("mode", mode))
+            .bind(("subscription", subscription))
+            .await?
+            .better_check()?;
+        let subscription = result.take::<Option<Record<Subscription>>>(1)?.found()?;
+        Ok(subscription)
+    }
+
+    /// Live subscriptions matching a newly opened round, dropping expired ones on the way.
+    /// Rounds of lobbies and parties never match.
+    pub async fn get_matching(&self, round: &Record<Round>) -> DbResult<Vec<Record<Subscription>>> {
+        if round.lobby.is_some() || round.party.is_some() {
+            return Ok(Vec::new());
+        }
+        let mut result = self
+            .db
+            .query("begin")
+            .query("delete subscription where <datetime> expires_at <= <datetime> $now")
+            .query(
+                r"
+                select * from subscription
+                    where mode = $mode
+                    and (round_no is none or round_no = $round_no)
+                    and max_rating >= $rating
+                ",
+            )
+            .query("commit")
+            .bind(("now", Utc::now()))
+            .bind(("mode", round.mode))
+            .bind(("round_no", round.round_no))
+            .bind(("rating", round.rating))
+            .await?
+            .better_check()?;
+        let subscriptions = result.take::<Vec<Record<Subscription>>>(1)?;
+        Ok(subscriptions)
+    }
+
+    /// Uses up a subscription, `None` when it was used up already.
+    pub async fn take(&self, subscription: &Thing) -> DbResult<Option<Record<Subscription>>> {
+        let mut result = self
+            .db
+            .query("delete $subscription return before")
+            .bind(("subscription", subscription))
+            .await?
+            .better_check()?;
+        let subscription = result.take::<Option<Record<Subscription>>>(0)?;
+        Ok(subscription)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SubscriptionRepository;
+    use crate::services::{
+        database::{round::RoundRepository, tests::db, user::UserRepository},
+        gamemodes::Mode,
+        provider::Provider,
+    };
+    use chrono::Duration;
+
+    async fn setup() -> (UserRepository, RoundRepository, SubscriptionRepository) {
+        let db = db().await;
+        (db.get(), db.get(), db.get())
+    }
+
+    #[tokio::test]
+    async fn take_matching_subscriptions_once() {
+        let (users, rounds, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let user2 = users.create_or_update_user(2, "").await.unwrap();
+        let user3 = users.create_or_update_user(3, "").await.unwrap();
+        let ttl = Duration::hours(1);
+        sut.subscribe(&user1, Mode::Ross, Some(0), 0, false, ttl)
+            .await
+            .unwrap();
+        sut.subscribe(&user1, Mode::Ross, None, 0, true, ttl)
+            .await
+            .unwrap();
+        sut.subscribe(&user2, Mode::Ross, Some(1), 0, false, ttl)
+            .await
+            .unwrap();
+        sut.subscribe(&user3, Mode::Evolution, None, 0, false, ttl)
+            .await
+            .unwrap();
+        let round = rounds
+            .attempt_new_round(&user0, Mode::Ross, 0, None, 2, Duration::seconds(60))
+            .await
+            .unwrap();
+
+        let matching = sut.get_matching(&round.round).await.unwrap();
+        let taken = sut.take(&matching[0].id).await.unwrap();
+        let taken_again = sut.take(&matching[0].id).await.unwrap();
+        let again = sut.get_matching(&round.round).await.unwrap();
+
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].user, user1.id);
+        assert!(matching[0].auto_claim);
+        assert!(taken.is_some());
+        assert!(taken_again.is_none());
+        assert!(again.is_empty());
+    }
+
+    #[tokio::test]
+    async fn skip_expired_and_higher_rated_subscriptions() {
+        let (users, rounds, sut) = setup().await;
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let user2 = users.create_or_update_user(2, "").await.unwrap();
+        sut.subscribe(&user1, Mode::Ross, None, 2, false, Duration::seconds(-1))
+            .await
+            .unwrap();
+        sut.subscribe(&user2, Mode::Ross, None, 0, false, Duration::hours(1))
+            .await
+            .unwrap();
+        let round = rounds
+            .attempt_new_round(&user0, Mode::Ross, 1, None, 2, Duration::seconds(60))
+            .await
+            .unwrap();
+
+        let matching = sut.get_matching(&round.round).await.unwrap();
+
+        assert!(matching.is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+use super::{Database, DbResult, MapToNotFound, Record};
+use crate::services::provider::Provider;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+pub struct ThemeRepository {
+    db: Database,
+}
+
+impl<T> Provider<ThemeRepository> for T
+where
+    T: Provider<Database>,
+{
+    fn get(&self) -> ThemeRepository {
+        ThemeRepository { db: self.get() }
+    }
+}
+
+impl ThemeRepository {
+    pub const TABLE: &str = "theme";
+
+    pub async fn create(&self, theme: Theme) -> DbResult<Record<Theme>> {
+        let theme = self
+            .db
+            .create::<Option<Record<Theme>>>((Self::TABLE, theme.name.as_str()))
+            .content(&theme)
+            .await?
+            .found()?;
+        Ok(theme)
+    }
+
+    pub async fn delete(&self, name: &str) -> DbResult<()> {
+        self.db
+            .delete::<Option<Record<Theme>>>((Self::TABLE, name))
+            .await?
+            .found()?;
+        Ok(())
+    }
+
+    pub async fn get(&self, name: &str) -> DbResult<Record<Theme>> {
+        self.db
+            .select::<Option<Record<Theme>>>((Self::TABLE, name))
+            .await?
+            .found()
+    }
+
+    pub async fn get_all(&self) -> DbResult<Vec<Record<Theme>>> {
+        let mut result = self
+            .db
+            .query("select * from theme order by starts_at")
+            .await?;
+        let themes = result.take::<Vec<Record<Theme>>>(0)?;
+        Ok(themes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Theme, ThemeRepository};
+    use crate::services::{
+        database::{round::RoundRepository, tests::db, user::UserRepository},
+        gamemodes::Mode,
+        provider::Provider,
+    };
+    use chrono::{Duration, Utc};
+
+    async fn setup() -> (UserRepository, RoundRepository, ThemeRepository) {
+        let db = db().await;
+        (db.get(), db.get(), db.get())
+    }
+
+    fn theme(name: &str, starts_in: Duration, ends_in: Duration) -> Theme {
+        let now = Utc::now();
+        Theme {
+            name: name.to_owned(),
+            starts_at: now + starts_in,
+            ends_at: now + ends_in,
+        }
+    }
+
+    #[tokio::test]
+    async fn fail_second_create() {
+        let (_, _, sut) = setup().await;
+
+        sut.create(theme("Space", Duration::zero(), Duration::hours(1)))
+            .await
+            .unwrap();
+        sut.create(theme("Space", Duration::zero(), Duration::hours(1)))
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn create_then_delete() {
+        let (_, _, sut) = setup().await;
+
+        sut.create(theme("Space", Duration::zero(), Duration::hours(1)))
+            .await
+            .unwrap();
+        sut.delete("Space").await.unwrap();
+
+        sut.get("Space").await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn new_round_gets_active_theme() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        sut.create(theme("Space", -Duration::hours(1), Duration::hours(1)))
+            .await
+            .unwrap();
+
+        let round = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::zero())
+            .await
+            .unwrap();
+
+        assert_eq!(round.round.theme.as_deref(), Some("Space"));
+    }
+
+    #[tokio::test]
+    async fn new_round_ignores_inactive_themes() {
+        let (users, rounds, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        sut.create(theme("Past", -Duration::hours(2), -Duration::hours(1)))
+            .await
+            .unwrap();
+        sut.create(theme("Future", Duration::hours(1), Duration::hours(2)))
+            .await
+            .unwrap();
+
+        let round = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 1, Duration::zero())
+            .await
+            .unwrap();
+
+        assert_eq!(round.round.theme, None);
+    }
+}
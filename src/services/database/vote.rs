@@ -0,0 +1,306 @@
+use super::{user::User, Record};
+use crate::services::{
+    database::{BetterCheck, Database, DbResult, MapToNotFound},
+    provider::Provider,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Deserialize)]
+pub struct Vote {
+    #[serde(rename = "in")]
+    pub who: Thing,
+    #[serde(rename = "out")]
+    pub what: Thing,
+    pub round: Thing,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Vote along with the draft image the user's previous vote in the round went to, if it was replaced.
+#[derive(Debug, Deserialize)]
+pub struct Ballot {
+    pub vote: Record<Vote>,
+    pub replaced: Option<u64>,
+}
+
+pub struct VoteRepository {
+    db: Database,
+}
+
+impl<T> Provider<VoteRepository> for T
+where
+    T: Provider<Database>,
+{
+    fn get(&self) -> VoteRepository {
+        VoteRepository { db: self.get() }
+    }
+}
+
+impl VoteRepository {
+    /// Votes for the draft posted as `image_id`, replacing the user's previous vote in that round.
+    /// Drafts of rounds that are not being voted on and the user's own drafts cannot be voted for.
+    pub async fn cast(&self, user: &Record<User>, image_id: u64) -> DbResult<Ballot> {
+        let now = Utc::now();
+        let mut result = self
+            .db
+            .query("begin")
+            .query("let $attempt = select * from only attempt where state.type is $state_type and state.what is $what")
+            .bind(("state_type", "Approved"))
+            .bind(("what", image_id))
+            .query(
+                r"
+                let $ballot = if $attempt is not none and $attempt.out.status.type is $status_type and $attempt.in is not $user {
+                    let $replaced = delete vote where in is $user and round is $attempt.out return before;
+                    let $vote = relate only $user -> vote -> $attempt content { round: $attempt.out, created_at: $now };
+                    return { vote: $vote, replaced: array::first($replaced).out.state.what };
+                }
+                ",
+            )
+            .bind(("user", &user.id))
+            .bind(("status_type", "Voting"))
+            .bind(("now", now))
+            .query("commit")
+            .query("return $ballot")
+            .await?
+            .better_check()?;
+        let ballot = result.take::<Option<Ballot>>(2)?.found()?;
+        Ok(ballot)
+    }
+
+    pub async fn retract(&self, user: &Record<User>, image_id: u64) -> DbResult<()> {
+        self.db
+            .query(
+                r"
+                delete vote
+                    where in is $user
+                    and out.state.what is $what
+                    and out.out.status.type is $status_type
+                ",
+            )
+            .bind(("user", &user.id))
+            .bind(("what", image_id))
+            .bind(("status_type", "Voting"))
+            .await?
+            .better_check()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VoteRepository;
+    use crate::services::{
+        database::{
+            attempt::{Approved, AttemptRepository},
+            round::{Fairness, Round, RoundRepository, RoundWithAttempts},
+            tests::db,
+            user::UserRepository,
+            Record,
+        },
+        gamemodes::Mode,
+        provider::Provider,
+    };
+    use chrono::{Duration, Utc};
+
+    async fn setup() -> (
+        UserRepository,
+        RoundRepository,
+        AttemptRepository,
+        VoteRepository,
+    ) {
+        let db = db().await;
+        (db.get(), db.get(), db.get(), db.get())
+    }
+
+    /// Two drafts of a single competitive round, approved as images 0 and 1.
+    async fn two_drafts(
+        users: &UserRepository,
+        rounds: &RoundRepository,
+        attempts: &AttemptRepository,
+    ) -> Record<Round> {
+        let mode = Mode::Ross;
+        let time_limit = Duration::hours(1);
+        let user0 = users.create_or_update_user(0, "").await.unwrap();
+        let user1 = users.create_or_update_user(1, "").await.unwrap();
+        let active0 = rounds
+            .attempt_new_round(&user0, mode, 0, None, 2, time_limit)
+            .await
+            .unwrap();
+        let active1 = rounds
+            .attempt_existing_round(&user1, mode, 0, None, 0, time_limit, &Fairness::default())
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user0, &active0.attempt.id)
+            .await
+            .unwrap();
+        let round = attempts
+            .approve_uploaded_attempt(&user0, &active0.attempt.id, 0)
+            .await
+            .unwrap();
+        let not_yet = rounds
+            .try_open_voting(&round.round, Utc::now())
+            .await
+            .unwrap();
+        assert!(not_yet.is_none());
+        attempts
+            .upload_active_attempt(&user1, &active1.attempt.id)
+            .await
+            .unwrap();
+        attempts
+            .approve_uploaded_attempt(&user1, &active1.attempt.id, 1)
+            .await
+            .unwrap();
+        rounds
+            .try_open_voting(&round.round, Utc::now())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    async fn close(rounds: &RoundRepository, round: &Record<Round>) -> RoundWithAttempts<Approved> {
+        let closed = rounds
+            .close_voting(round, round.forward(None))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(closed.next.round.round_no, 1);
+        assert_eq!(closed.next.previous.len(), 1);
+        closed.winner
+    }
+
+    #[tokio::test]
+    async fn most_voted_draft_wins() {
+        let (users, rounds, attempts, sut) = setup().await;
+        let round = two_drafts(&users, &rounds, &attempts).await;
+        let voter0 = users.create_or_update_user(2, "").await.unwrap();
+        let voter1 = users.create_or_update_user(3, "").await.unwrap();
+
+        sut.cast(&voter0, 1).await.unwrap();
+        sut.cast(&voter1, 1).await.unwrap();
+        let winner = close(&rounds, &round).await;
+
+        assert_eq!(winner.attempt.state.what, 1);
+    }
+
+    #[tokio::test]
+    async fn recast_vote_replaces_previous() {
+        let (users, rounds, attempts, sut) = setup().await;
+        let round = two_drafts(&users, &rounds, &attempts).await;
+        let voter = users.create_or_update_user(2, "").await.unwrap();
+
+        let first = sut.cast(&voter, 1).await.unwrap();
+        let second = sut.cast(&voter, 0).await.unwrap();
+        let winner = close(&rounds, &round).await;
+
+        assert_eq!(first.replaced, None);
+        assert_eq!(second.replaced, Some(1));
+        assert_eq!(winner.attempt.state.what, 0);
+    }
+
+    #[tokio::test]
+    async fn fail_voting_for_own_draft() {
+        let (users, rounds, attempts, sut) = setup().await;
+        two_drafts(&users, &rounds, &attempts).await;
+        let author = users.create_or_update_user(1, "").await.unwrap();
+
+        sut.cast(&author, 1).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn fail_voting_before_voting_opens() {
+        let (users, rounds, attempts, sut) = setup().await;
+        let user = users.create_or_update_user(0, "").await.unwrap();
+        let voter = users.create_or_update_user(1, "").await.unwrap();
+        let active = rounds
+            .attempt_new_round(&user, Mode::Ross, 0, None, 2, Duration::hours(1))
+            .await
+            .unwrap();
+        attempts
+            .upload_active_attempt(&user, &active.attempt.id)
+            .await
+            .unwrap();
+        attempts
+            .approve_uploaded_attempt(&user, &active.attempt.id, 0)
+            .await
+            .unwrap();
+
+        sut.cast(&voter, 0).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn retracted_vote_is_not_counted() {
+        let (users, rounds, attempts, sut) = setup().await;
+        let round = two_drafts(&users, &rounds, &attempts).await;
+        let voter = users.create_or_update_user(2, "").await.unwrap();
+
+        sut.cast(&voter, 1).await.unwrap();
+        sut.retract(&voter, 1).await.unwrap();
+        let winner = close(&rounds, &round).await;
+
+        assert_eq!(winner.attempt.state.what, 0);
+    }
+
+    #[tokio::test]
+    async fn list_expired_votings() {
+        let (users, rounds, attempts, _) = setup().await;
+        two_drafts(&users, &rounds, &attempts).await;
+
+        let expired = rounds
+            .get_expired_votings(Utc::now() + Duration::seconds(1))
+            .await
+            .unwrap();
+
+        assert_eq!(expired.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn forward_closed_voting_once() {
+        let (users, rounds, attempts, _) = setup().await;
+        let round = two_drafts(&users, &rounds, &attempts).await;
+
+        close(&rounds, &round).await;
+        let again = rounds
+            .close_voting(&round, round.forward(None))
+            .await
+            .unwrap();
+
+        assert!(again.is_none());
+    }
+
+    #[tokio::test]
+    async fn reopen_voting_without_approved_drafts() {
+        let (users, rounds, attempts, _) = setup().await;
+        let round = two_drafts(&users, &rounds, &attempts).await;
+        let moderator = users.create_or_update_user(2, "").await.unwrap();
+        attempts
+            .take_down_approved_attempt(&moderator, 0)
+            .await
+            .unwrap();
+        attempts
+            .take_down_approved_attempt(&moderator, 1)
+            .await
+            .unwrap();
+
+        let closed = rounds
+            .close_voting(&round, round.forward(None))
+            .await
+            .unwrap();
+        let reopened = rounds
+            .attempt_existing_round(
+                &moderator,
+                Mode::Ross,
+                0,
+                None,
+                0,
+                Duration::hours(1),
+                &Fairness::default(),
+            )
+            .await
+            .unwrap();
+
+        assert!(closed.is_none());
+        assert_eq!(reopened.round.id, round.id);
+    }
+}
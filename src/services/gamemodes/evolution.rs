@@ -1,4 +1,4 @@
-use super::GameLogic;
+use super::{Canvas, GameLogic};
 use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
@@ -29,4 +29,8 @@ impl GameLogic for Evolution {
     fn multiplex(&self, _round_no: u64) -> u64 {
         1
     }
+
+    fn canvas(&self, _round_no: u64) -> Canvas {
+        Canvas::new(480, 720)
+    }
 }
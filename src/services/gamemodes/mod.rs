@@ -24,12 +24,26 @@ impl GameSession {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     Ross,
     Evolution,
 }
 
+impl Mode {
+    pub const ALL: [Mode; 2] = [Mode::Ross, Mode::Evolution];
+
+    /// Smallest canvas that every round of every mode fits into.
+    pub fn max_canvas() -> Canvas {
+        Self::ALL
+            .iter()
+            .flat_map(|mode| (0..=mode.last_round()).map(|round_no| mode.canvas(round_no)))
+            .fold(Canvas::new(0, 0), |max, canvas| {
+                Canvas::new(max.width.max(canvas.width), max.height.max(canvas.height))
+            })
+    }
+}
+
 impl GameLogic for Mode {
     fn last_round(&self) -> u64 {
         match self {
@@ -58,6 +72,13 @@ impl GameLogic for Mode {
             Mode::Evolution => Evolution.multiplex(round_no),
         }
     }
+
+    fn canvas(&self, round_no: u64) -> Canvas {
+        match self {
+            Mode::Ross => Ross.canvas(round_no),
+            Mode::Evolution => Evolution.canvas(round_no),
+        }
+    }
 }
 
 pub trait GameLogic {
@@ -65,6 +86,19 @@ pub trait GameLogic {
     fn time_limit(&self, round_no: u64) -> Duration;
     fn prompt(&self, round_no: u64) -> &'static str;
     fn multiplex(&self, round_no: u64) -> u64;
+    fn canvas(&self, round_no: u64) -> Canvas;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Canvas {
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -1,13 +1,32 @@
-use super::GameLogic;
+use super::{Canvas, GameLogic};
 use chrono::Duration;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static ATTRIBUTES: AtomicU64 = AtomicU64::new(Ross::DEFAULT_ATTRIBUTES);
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct Ross;
 
+impl Ross {
+    pub const DEFAULT_ATTRIBUTES: u64 = 4;
+    /// Attribute counts which fill a close to square grid.
+    pub const SUPPORTED_ATTRIBUTES: [u64; 5] = [2, 3, 4, 6, 9];
+
+    /// Number of attribute rounds preceding the final character round.
+    pub fn attributes() -> u64 {
+        ATTRIBUTES.load(Ordering::Relaxed)
+    }
+
+    /// Meant to be called once at startup, before any round is played.
+    pub fn set_attributes(attributes: u64) {
+        ATTRIBUTES.store(attributes, Ordering::Relaxed);
+    }
+}
+
 impl GameLogic for Ross {
     fn last_round(&self) -> u64 {
-        4
+        Self::attributes()
     }
 
     fn time_limit(&self, round_no: u64) -> Duration {
@@ -27,10 +46,18 @@ impl GameLogic for Ross {
     }
 
     fn multiplex(&self, round_no: u64) -> u64 {
-        match round_no {
-            0 => 1,
-            1..=4 => 1,
-            _ => 0,
+        if round_no <= self.last_round() {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn canvas(&self, round_no: u64) -> Canvas {
+        if round_no < self.last_round() {
+            Canvas::new(512, 512)
+        } else {
+            Canvas::new(1024, 1024)
         }
     }
 }
@@ -1,3 +1,4 @@
+use ab_glyph::{point, Font, PxScale, ScaleFont};
 use async_trait::async_trait;
 use bytes::{Bytes, BytesMut};
 use image::{
@@ -10,6 +11,8 @@ use std::{cmp::max, io::Cursor, path::Path};
 use tokio::{fs::OpenOptions, io::AsyncReadExt};
 
 const WHITE: Rgba<u8> = Rgba([255u8, 255u8, 255u8, 255u8]);
+const BLACK: Rgba<u8> = Rgba([0u8, 0u8, 0u8, 255u8]);
+const TRANSPARENT: Rgba<u8> = Rgba([0u8, 0u8, 0u8, 0u8]);
 
 #[async_trait]
 pub trait RgbaConvert {
@@ -138,32 +141,150 @@ impl RgbaProcess for RgbaImage {
     }
 }
 
-pub fn concat_2_2(images: &[RgbaImage]) -> RgbaImage {
-    assert!(images.len() == 4);
-    let h = images.iter().map(|i| i.height()).max().unwrap();
-    let w = images.iter().map(|i| i.width()).max().unwrap();
-    let mut concated = ImageBuffer::new(w * 2, h * 2);
-    for (i, img) in images.iter().enumerate() {
-        let i = i as u32;
-        let x = (i % 2) * w;
-        let y = (i / 2) * h;
-        concated.copy_from(img, x, y).unwrap();
+pub struct Cell<'a> {
+    pub image: &'a RgbaImage,
+    pub label: Option<&'a RgbaImage>,
+}
+
+impl<'a> Cell<'a> {
+    pub fn new(image: &'a RgbaImage) -> Self {
+        Self { image, label: None }
+    }
+
+    pub fn labelled(image: &'a RgbaImage, label: &'a RgbaImage) -> Self {
+        Self {
+            image,
+            label: Some(label),
+        }
     }
-    concated
 }
 
-pub fn concat_vertical(images: &[RgbaImage]) -> RgbaImage {
-    let mut delta_height = 0;
-    let w = images.iter().map(|i| i.width()).max().unwrap();
-    let total_height = images.iter().map(|i| i.height()).sum();
-    let mut concated = ImageBuffer::new(w, total_height);
-    for img in images.iter() {
-        let x = 0;
-        let y = delta_height;
-        delta_height += img.height();
-        concated.copy_from(img, x, y).unwrap();
+#[derive(Debug, Clone, Copy)]
+pub struct Grid {
+    pub columns: u32,
+    pub rows: u32,
+    pub gutter: u32,
+    pub border: u32,
+    pub background: Rgba<u8>,
+}
+
+impl Grid {
+    pub fn new(columns: u32, rows: u32) -> Self {
+        Self {
+            columns,
+            rows,
+            gutter: 0,
+            border: 0,
+            background: TRANSPARENT,
+        }
+    }
+
+    /// Smallest, close to square grid that fits `n` cells.
+    pub fn fitting(n: u32) -> Self {
+        let columns = (n as f64).sqrt().ceil().max(1.0) as u32;
+        let rows = n.div_ceil(columns).max(1);
+        Self::new(columns, rows)
+    }
+
+    pub fn gutter(mut self, gutter: u32) -> Self {
+        self.gutter = gutter;
+        self
+    }
+
+    pub fn border(mut self, border: u32) -> Self {
+        self.border = border;
+        self
+    }
+
+    pub fn background(mut self, background: Rgba<u8>) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Places cells row by row, centering every image in a uniformly sized cell with its label below.
+    pub fn compose(&self, cells: &[Cell]) -> RgbaImage {
+        assert!(cells.len() as u32 <= self.columns * self.rows);
+        let w = cells.iter().map(|c| c.image.width()).max().unwrap_or(0);
+        let h = cells.iter().map(|c| c.image.height()).max().unwrap_or(0);
+        let label_h = cells
+            .iter()
+            .filter_map(|c| c.label.map(|l| l.height()))
+            .max()
+            .unwrap_or(0);
+        let cell_h = h + label_h;
+        let total_width =
+            2 * self.border + self.columns * w + self.columns.saturating_sub(1) * self.gutter;
+        let total_height =
+            2 * self.border + self.rows * cell_h + self.rows.saturating_sub(1) * self.gutter;
+        let mut composed = RgbaImage::from_pixel(total_width, total_height, self.background);
+        for (i, cell) in cells.iter().enumerate() {
+            let i = i as u32;
+            let x = self.border + (i % self.columns) * (w + self.gutter);
+            let y = self.border + (i / self.columns) * (cell_h + self.gutter);
+            let (image_w, image_h) = cell.image.dimensions();
+            composed
+                .copy_from(cell.image, x + (w - image_w) / 2, y + (h - image_h) / 2)
+                .unwrap();
+            if let Some(label) = cell.label {
+                let label_x = x + w.saturating_sub(label.width()) / 2;
+                composed.copy_from(label, label_x, y + h).unwrap();
+            }
+        }
+        composed
+    }
+}
+
+/// Renders a single line of centered black text on a white strip, clipping whatever does not fit.
+pub fn render_label(font: &impl Font, text: &str, width: u32, height: u32) -> RgbaImage {
+    let mut label = RgbaImage::from_pixel(width, height, WHITE);
+    let font = font.as_scaled(PxScale::from(height as f32 * 0.75));
+
+    let mut glyphs = Vec::new();
+    let mut caret = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += font.kern(previous, id);
+        }
+        glyphs.push(id.with_scale_and_position(font.scale(), point(caret, font.ascent())));
+        caret += font.h_advance(id);
+        previous = Some(id);
     }
-    concated
+
+    let x_offset = ((width as f32 - caret) / 2.0).max(0.0);
+    let y_offset = (height as f32 - font.height()) / 2.0;
+    for glyph in glyphs {
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|x, y, coverage| {
+            let x = (x as f32 + bounds.min.x + x_offset) as i64;
+            let y = (y as f32 + bounds.min.y + y_offset) as i64;
+            if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                let mut ink = BLACK;
+                ink.0[3] = (coverage * 255.0) as u8;
+                label.get_pixel_mut(x as u32, y as u32).blend(&ink);
+            }
+        });
+    }
+    label
+}
+
+pub fn concat_grid(images: &[RgbaImage]) -> RgbaImage {
+    let cells = images.iter().map(Cell::new).collect::<Vec<_>>();
+    Grid::fitting(images.len() as u32).compose(&cells)
+}
+
+pub fn concat_vertical(images: &[RgbaImage]) -> RgbaImage {
+    let cells = images.iter().map(Cell::new).collect::<Vec<_>>();
+    Grid::new(1, images.len() as u32).compose(&cells)
+}
+
+/// Shrinks the image to fit the bounds without padding it to their size.
+pub fn fit_image(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    image.remove_alpha().scale_to_fit(width, height)
 }
 
 pub fn normalize_image(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
@@ -186,6 +307,64 @@ pub fn normalize_images_aoi(images: &[&RgbaImage], width: u32, height: u32) -> V
 
 pub fn combined(images: &[&RgbaImage], width: u32, height: u32) -> Vec<u8> {
     let images = normalize_images_aoi(images, width, height);
-    let image = concat_2_2(&images);
+    let image = concat_grid(&images);
     image.to_png()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Cell, Grid, TRANSPARENT, WHITE};
+    use image::RgbaImage;
+
+    #[test]
+    fn fit_grid_close_to_square() {
+        let sizes = [0, 1, 2, 3, 4, 5, 9, 10]
+            .map(Grid::fitting)
+            .map(|grid| (grid.columns, grid.rows));
+
+        assert_eq!(
+            sizes,
+            [
+                (1, 1),
+                (1, 1),
+                (2, 1),
+                (2, 2),
+                (2, 2),
+                (3, 2),
+                (3, 3),
+                (4, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn size_grid_by_largest_cell() {
+        let small = RgbaImage::new(10, 20);
+        let large = RgbaImage::new(30, 5);
+        let label = RgbaImage::new(30, 4);
+        let cells = [Cell::new(&small), Cell::labelled(&large, &label)];
+
+        let composed = Grid::new(3, 2).gutter(2).border(1).compose(&cells);
+
+        assert_eq!(composed.dimensions(), (2 + 3 * 30 + 2 * 2, 2 + 2 * 24 + 2));
+    }
+
+    #[test]
+    fn compose_empty_grid() {
+        let composed = Grid::new(0, 0).border(1).compose(&[]);
+
+        assert_eq!(composed.dimensions(), (2, 2));
+    }
+
+    #[test]
+    fn fill_grid_with_background() {
+        let image = RgbaImage::from_pixel(1, 1, WHITE);
+        let cells = [Cell::new(&image)];
+
+        let transparent = Grid::new(2, 1).compose(&cells);
+        let white = Grid::new(2, 1).background(WHITE).compose(&cells);
+
+        assert_eq!(*transparent.get_pixel(1, 0), TRANSPARENT);
+        assert_eq!(*white.get_pixel(1, 0), WHITE);
+    }
+}